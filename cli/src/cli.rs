@@ -114,6 +114,20 @@ pub struct RunCmd {
 	/// commonly `127.0.0.1:6831`.
 	#[structopt(long)]
 	pub jaeger_agent: Option<std::net::SocketAddr>,
+
+	/// Path to a JSON file with the relay chain selection parameters of this node.
+	///
+	/// Omitted parameters keep their defaults, e.g. `{ "maxFinalityLag": 50 }` only
+	/// sets the maximum finality lag.
+	#[structopt(long, parse(from_os_str))]
+	pub chain_selection_config: Option<std::path::PathBuf>,
+
+	/// Milliseconds to wait for the provisioner's parachain inherent data when authoring a block.
+	///
+	/// If the provisioner does not respond in time, the block is authored without parachain
+	/// data. Defaults to 2500.
+	#[structopt(long)]
+	pub provisioner_timeout: Option<u64>,
 }
 
 #[allow(missing_docs)]
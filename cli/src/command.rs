@@ -215,6 +215,13 @@ fn run_node_inner(cli: Cli, overseer_gen: impl service::OverseerGen) -> Result<(
 
 	let jaeger_agent = cli.run.jaeger_agent;
 
+	let select_chain_config = match cli.run.chain_selection_config {
+		Some(ref path) => service::relay_chain_selection::ChainSelectionConfig::from_file(path)?,
+		None => Default::default(),
+	};
+
+	let provisioner_timeout = cli.run.provisioner_timeout.map(std::time::Duration::from_millis);
+
 	runner.run_node_until_exit(move |config| async move {
 		let role = config.role.clone();
 
@@ -229,6 +236,8 @@ fn run_node_inner(cli: Cli, overseer_gen: impl service::OverseerGen) -> Result<(
 				grandpa_pause,
 				cli.run.no_beefy,
 				jaeger_agent,
+				select_chain_config,
+				provisioner_timeout,
 				None,
 				overseer_gen,
 			).map(|full| full.task_manager).map_err(Into::into)
@@ -36,8 +36,8 @@ use sp_blockchain::HeaderBackend;
 use sp_runtime::generic::BlockId;
 use std::time;
 
-/// How long to wait for the provisioner, before giving up.
-const PROVISIONER_TIMEOUT: time::Duration = core::time::Duration::from_millis(2500);
+/// How long to wait for the provisioner by default, before giving up.
+pub const DEFAULT_PROVISIONER_TIMEOUT: time::Duration = core::time::Duration::from_millis(2500);
 
 /// Provides the parachains inherent data.
 pub struct ParachainsInherentDataProvider {
@@ -47,9 +47,23 @@ pub struct ParachainsInherentDataProvider {
 impl ParachainsInherentDataProvider {
 	/// Create a new instance of the [`ParachainsInherentDataProvider`].
 	pub async fn create<C: HeaderBackend<Block>>(
+		client: &C,
+		overseer: Handle,
+		parent: Hash,
+	) -> Result<Self, Error> {
+		Self::create_with_timeout(client, overseer, parent, DEFAULT_PROVISIONER_TIMEOUT).await
+	}
+
+	/// Create a new instance of the [`ParachainsInherentDataProvider`], waiting at most
+	/// `provisioner_timeout` for the provisioner.
+	///
+	/// If the provisioner does not respond in time, empty inherent data is provided, so that
+	/// slow machines still author their blocks within the slot.
+	pub async fn create_with_timeout<C: HeaderBackend<Block>>(
 		client: &C,
 		mut overseer: Handle,
 		parent: Hash,
+		provisioner_timeout: time::Duration,
 	) -> Result<Self, Error> {
 		let pid = async {
 			let (sender, receiver) = futures::channel::oneshot::channel();
@@ -65,7 +79,7 @@ impl ParachainsInherentDataProvider {
 			receiver.await.map_err(|_| Error::ClosedChannelAwaitingInherentData)
 		};
 
-		let mut timeout = futures_timer::Delay::new(PROVISIONER_TIMEOUT).fuse();
+		let mut timeout = futures_timer::Delay::new(provisioner_timeout).fuse();
 
 		let parent_header = match client.header(BlockId::Hash(parent)) {
 			Ok(Some(h)) => h,
@@ -75,7 +89,7 @@ impl ParachainsInherentDataProvider {
 
 		let res = select! {
 			pid = pid.fuse() => pid,
-			_ = timeout => Err(Error::Timeout),
+			_ = timeout => Err(Error::Timeout(provisioner_timeout)),
 		};
 
 		let inherent_data = match res {
@@ -126,8 +140,8 @@ impl sp_inherents::InherentDataProvider for ParachainsInherentDataProvider {
 pub enum Error {
 	#[error("Blockchain error")]
 	Blockchain(#[from] sp_blockchain::Error),
-	#[error("Timeout: provisioner did not return inherent data after {:?}", PROVISIONER_TIMEOUT)]
-	Timeout,
+	#[error("Timeout: provisioner did not return inherent data after {0:?}")]
+	Timeout(time::Duration),
 	#[error("Could not find the parent header in the blockchain: {:?}", _0)]
 	ParentHeaderNotFound(Hash),
 	#[error("Closed channel from overseer when awaiting activation")]
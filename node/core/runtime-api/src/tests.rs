@@ -20,7 +20,8 @@ use polkadot_primitives::v1::{
 	ValidatorId, ValidatorIndex, GroupRotationInfo, CoreState, PersistedValidationData,
 	Id as ParaId, OccupiedCoreAssumption, SessionIndex, ValidationCode,
 	CommittedCandidateReceipt, CandidateEvent, InboundDownwardMessage,
-	InboundHrmpMessage, SessionInfo, AuthorityDiscoveryId, ValidationCodeHash,
+	InboundHrmpMessage, SessionInfo, AuthorityDiscoveryId, ValidationCodeHash, BlockNumber,
+	CandidateHash,
 };
 use polkadot_node_subsystem_test_helpers as test_helpers;
 use sp_core::testing::TaskExecutor;
@@ -48,6 +49,14 @@ struct MockRuntimeApi {
 	dmq_contents: HashMap<ParaId, Vec<InboundDownwardMessage>>,
 	hrmp_channels: HashMap<ParaId, BTreeMap<ParaId, Vec<InboundHrmpMessage>>>,
 	babe_epoch: Option<BabeEpoch>,
+	last_backed_candidate_weight: u64,
+	dry_run_inherent_weight: u64,
+	pending_availability_count: u32,
+	pending_bitfields: Vec<ValidatorIndex>,
+	last_included_block_number: Option<BlockNumber>,
+	scheduled_cores_count: u32,
+	availability_core_occupancy: Vec<Option<ParaId>>,
+	last_backed_candidate_hashes: Vec<CandidateHash>,
 }
 
 impl ProvideRuntimeApi<Block> for MockRuntimeApi {
@@ -148,6 +157,38 @@ sp_api::mock_impl_runtime_apis! {
 		) -> Option<ValidationCode> {
 			self.validation_code_by_hash.get(&hash).map(|c| c.clone())
 		}
+
+		fn last_backed_candidate_weight(&self) -> u64 {
+			self.last_backed_candidate_weight
+		}
+
+		fn dry_run_inherent(&self, _data: polkadot_primitives::v1::InherentData) -> Result<u64, sp_runtime::DispatchError> {
+			Ok(self.dry_run_inherent_weight)
+		}
+
+		fn pending_availability_count(&self) -> u32 {
+			self.pending_availability_count
+		}
+
+		fn pending_bitfields(&self) -> Vec<ValidatorIndex> {
+			self.pending_bitfields.clone()
+		}
+
+		fn last_included_block_number(&self) -> Option<BlockNumber> {
+			self.last_included_block_number
+		}
+
+		fn scheduled_cores_count(&self) -> u32 {
+			self.scheduled_cores_count
+		}
+
+		fn availability_core_occupancy(&self) -> Vec<Option<ParaId>> {
+			self.availability_core_occupancy.clone()
+		}
+
+		fn last_backed_candidate_hashes(&self) -> Vec<CandidateHash> {
+			self.last_backed_candidate_hashes.clone()
+		}
 	}
 
 	impl BabeApi<Block> for MockRuntimeApi {
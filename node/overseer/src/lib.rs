@@ -188,6 +188,19 @@ impl Handle {
 		self.send_and_log_error(Event::MsgToSubsystem { msg: msg.into(), origin }).await
 	}
 
+	/// Try to send some message to one of the `Subsystem`s without waiting.
+	///
+	/// Returns whether the message was handed over to the `Overseer`, which fails if the handle
+	/// is disconnected or the channel to the `Overseer` is full or closed.
+	pub fn try_send_msg(&mut self, msg: impl Into<AllMessages>, origin: &'static str) -> bool {
+		self.try_connect();
+		match self {
+			Self::Connected(ref mut handle) =>
+				handle.try_send(Event::MsgToSubsystem { msg: msg.into(), origin }).is_ok(),
+			Self::Disconnected(_) => false,
+		}
+	}
+
 	/// Send a message not providing an origin.
 	#[inline(always)]
 	pub async fn send_msg_anon(&mut self, msg: impl Into<AllMessages>) {
@@ -237,7 +250,19 @@ impl Handle {
 		}
 	}
 
+	/// Whether the handle was connected to an overseer which is no longer running.
+	pub fn is_closed(&self) -> bool {
+		match self {
+			Self::Disconnected(ref x) => x.read().as_ref().map_or(false, |handle| handle.is_closed()),
+			Self::Connected(ref handle) => handle.is_closed(),
+		}
+	}
+
 	/// Connect this handle and all disconnected clones of it to the overseer.
+	///
+	/// If the overseer they were connected to is no longer running, they are reconnected to the
+	/// given one. Clones which were upgraded to the connected state by sending through them are
+	/// not affected.
 	pub fn connect_to_overseer(&mut self, handle: OverseerHandle) {
 		match self {
 			Self::Disconnected(ref mut x) => {
@@ -245,6 +270,9 @@ impl Handle {
 				if maybe_handle.is_none() {
 					tracing::info!(target: LOG_TARGET, "🖇️ Connecting all Handles to Overseer");
 					*maybe_handle = Some(handle);
+				} else if maybe_handle.as_ref().map_or(false, |h| h.is_closed()) {
+					tracing::info!(target: LOG_TARGET, "🖇️ Reconnecting all Handles to a new Overseer");
+					*maybe_handle = Some(handle);
 				} else {
 					tracing::warn!(
 						target: LOG_TARGET,
@@ -1080,3 +1080,21 @@ fn context_holds_onto_message_until_enough_signals_received() {
 
 	futures::executor::block_on(test_fut);
 }
+
+#[test]
+fn disconnected_handle_reconnects_to_a_restarted_overseer() {
+	let mut handle = overseer::Handle::new_disconnected();
+	let mut clone = handle.clone();
+
+	let (tx, rx) = metered::channel(1);
+	handle.connect_to_overseer(tx);
+	assert!(!clone.is_closed());
+
+	// The overseer stopped.
+	drop(rx);
+	assert!(clone.is_closed());
+
+	let (tx, _rx) = metered::channel(1);
+	clone.connect_to_overseer(tx);
+	assert!(!handle.is_closed());
+}
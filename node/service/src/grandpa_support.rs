@@ -28,6 +28,8 @@ use polkadot_primitives::v1::Hash;
 
 /// Returns the block hash of the block at the given `target_number` by walking
 /// backwards from the given `current_header`.
+///
+/// Fails if the `target_number` is above the number of the `current_header`.
 pub(super) fn walk_backwards_to_target_block<Block, HP>(
 	backend: &HP,
 	target_number: NumberFor<Block>,
@@ -42,11 +44,13 @@ where
 
 	loop {
 		if *target_header.number() < target_number {
-			unreachable!(
-				"we are traversing backwards from a known block; \
-				 blocks are stored contiguously; \
-				 qed"
-			);
+			// we are traversing backwards from a known block and blocks are stored
+			// contiguously, so this only happens if the target is above the current header.
+			return Err(sp_blockchain::Error::UnknownBlock(format!(
+				"Target block number {} is above the current block number {}",
+				target_number,
+				current_header.number(),
+			)));
 		}
 
 		if *target_header.number() == target_number {
@@ -21,7 +21,7 @@
 pub mod chain_spec;
 mod grandpa_support;
 mod parachains_db;
-mod relay_chain_selection;
+pub mod relay_chain_selection;
 
 #[cfg(feature = "full-node")]
 mod overseer;
@@ -148,6 +148,8 @@ where
 		&self,
 		hash: <Block as BlockT>::Hash,
 	) -> Result<Option<<<Block as BlockT>::Header as HeaderT>::Number>, Error>;
+	/// Obtain the number of the last finalized block.
+	fn finalized_number(&self) -> Result<<<Block as BlockT>::Header as HeaderT>::Number, Error>;
 }
 
 impl<Block, T> HeaderProvider<Block> for T
@@ -170,6 +172,11 @@ where
 	) -> sp_blockchain::Result<Option<<<Block as BlockT>::Header as HeaderT>::Number>> {
 		<Self as sp_blockchain::HeaderBackend<Block>>::number(self, hash)
 	}
+	fn finalized_number(
+		&self,
+	) -> sp_blockchain::Result<<<Block as BlockT>::Header as HeaderT>::Number> {
+		Ok(<Self as sp_blockchain::HeaderBackend<Block>>::info(self).finalized_number)
+	}
 }
 
 /// Decoupling the provider.
@@ -226,6 +233,9 @@ pub enum Error {
 	#[error(transparent)]
 	Jaeger(#[from] polkadot_subsystem::jaeger::JaegerError),
 
+	#[error("Invalid chain selection config")]
+	ChainSelectionConfig(#[from] serde_json::Error),
+
 	#[cfg(feature = "full-node")]
 	#[error(transparent)]
 	Availability(#[from] AvailabilityError),
@@ -320,6 +330,7 @@ type LightClient<RuntimeApi, Executor> =
 fn new_partial<RuntimeApi, Executor>(
 	config: &mut Configuration,
 	jaeger_agent: Option<std::net::SocketAddr>,
+	select_chain_config: relay_chain_selection::ChainSelectionConfig,
 	telemetry_worker_handle: Option<TelemetryWorkerHandle>,
 ) -> Result<
 	service::PartialComponents<
@@ -387,10 +398,19 @@ fn new_partial<RuntimeApi, Executor>(
 
 	jaeger_launch_collector_with_agent(task_manager.spawn_handle(), &*config, jaeger_agent)?;
 
-	let select_chain = relay_chain_selection::SelectRelayChainWithFallback::new(
+	let mut select_chain = relay_chain_selection::SelectRelayChainWithFallback::new_with_metrics(
 		backend.clone(),
 		Handle::new_disconnected(),
-		polkadot_node_subsystem_util::metrics::Metrics::register(config.prometheus_registry())?,
+		config.prometheus_registry(),
+		select_chain_config,
+	)?;
+	if let Some(telemetry) = telemetry.as_ref() {
+		select_chain.set_telemetry(telemetry.handle());
+	}
+
+	task_manager.spawn_handle().spawn(
+		"chain-selection-session-changes",
+		relay_chain_selection::forward_session_changes(client.clone(), select_chain.clone()),
 	);
 
 	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
@@ -498,6 +518,7 @@ fn new_partial<RuntimeApi, Executor>(
 					beefy_commitment_stream: beefy_commitment_stream.clone(),
 					subscription_executor,
 				},
+				chain_selection_health: Some(Arc::new(select_chain.clone())),
 			};
 
 			polkadot_rpc::create_full(deps)
@@ -632,6 +653,8 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 	grandpa_pause: Option<(u32, u32)>,
 	disable_beefy: bool,
 	jaeger_agent: Option<std::net::SocketAddr>,
+	select_chain_config: relay_chain_selection::ChainSelectionConfig,
+	provisioner_timeout: Option<std::time::Duration>,
 	telemetry_worker_handle: Option<TelemetryWorkerHandle>,
 	program_path: Option<std::path::PathBuf>,
 	overseer_gen: OverseerGenerator,
@@ -670,7 +693,7 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 		import_queue,
 		transaction_pool,
 		other: (rpc_extensions_builder, import_setup, rpc_setup, slot_duration, mut telemetry)
-	} = new_partial::<RuntimeApi, Executor>(&mut config, jaeger_agent, telemetry_worker_handle)?;
+	} = new_partial::<RuntimeApi, Executor>(&mut config, jaeger_agent, select_chain_config, telemetry_worker_handle)?;
 
 	let prometheus_registry = config.prometheus_registry().cloned();
 
@@ -849,6 +872,7 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 		)?;
 		let handle = Handle::Connected(overseer_handle.clone());
 		let handle_clone = handle.clone();
+		let overseer_select_chain = select_chain.clone();
 
 		task_manager.spawn_essential_handle().spawn_blocking("overseer", Box::pin(async move {
 			use futures::{pin_mut, select, FutureExt};
@@ -866,6 +890,11 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 				_ = overseer_fut => (),
 				complete => (),
 			}
+
+			// Fall back to the longest chain rule from now on, and cancel the pending requests,
+			// which the exited overseer no longer answers.
+			overseer_select_chain.disconnect();
+			overseer_select_chain.shutdown();
 		}));
 		// we should remove this check before we deploy parachains on polkadot
 		// TODO: https://github.com/paritytech/polkadot/issues/3326
@@ -899,6 +928,8 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 		let client_clone = client.clone();
 		let overseer_handle = overseer_handle.as_ref().ok_or(Error::AuthoritiesRequireRealOverseer)?.clone();
 		let slot_duration = babe_link.config().slot_duration();
+		let provisioner_timeout = provisioner_timeout
+			.unwrap_or(polkadot_node_core_parachains_inherent::DEFAULT_PROVISIONER_TIMEOUT);
 		let babe_config = babe::BabeParams {
 			keystore: keystore_container.sync_keystore(),
 			client: client.clone(),
@@ -911,10 +942,11 @@ pub fn new_full<RuntimeApi, Executor, OverseerGenerator>(
 				let client_clone = client_clone.clone();
 				let overseer_handle = overseer_handle.clone();
 				async move {
-					let parachain = polkadot_node_core_parachains_inherent::ParachainsInherentDataProvider::create(
+					let parachain = polkadot_node_core_parachains_inherent::ParachainsInherentDataProvider::create_with_timeout(
 						&*client_clone,
 						overseer_handle,
 						parent,
+						provisioner_timeout,
 					).await.map_err(|e| Box::new(e))?;
 
 					let uncles = sc_consensus_uncles::create_uncles_inherent_data_provider(
@@ -1225,26 +1257,26 @@ pub fn new_chain_ops(
 	#[cfg(feature = "rococo-native")]
 	if config.chain_spec.is_rococo() || config.chain_spec.is_wococo() {
 		let service::PartialComponents { client, backend, import_queue, task_manager, .. }
-			= new_partial::<rococo_runtime::RuntimeApi, RococoExecutor>(config, jaeger_agent, None)?;
+			= new_partial::<rococo_runtime::RuntimeApi, RococoExecutor>(config, jaeger_agent, Default::default(), None)?;
 		return Ok((Arc::new(Client::Rococo(client)), backend, import_queue, task_manager))
 	}
 
 	#[cfg(feature = "kusama-native")]
 	if config.chain_spec.is_kusama() {
 		let service::PartialComponents { client, backend, import_queue, task_manager, .. }
-			= new_partial::<kusama_runtime::RuntimeApi, KusamaExecutor>(config, jaeger_agent, None)?;
+			= new_partial::<kusama_runtime::RuntimeApi, KusamaExecutor>(config, jaeger_agent, Default::default(), None)?;
 		return Ok((Arc::new(Client::Kusama(client)), backend, import_queue, task_manager))
 	}
 
 	#[cfg(feature = "westend-native")]
 	if config.chain_spec.is_westend() {
 		let service::PartialComponents { client, backend, import_queue, task_manager, .. }
-			= new_partial::<westend_runtime::RuntimeApi, WestendExecutor>(config, jaeger_agent, None)?;
+			= new_partial::<westend_runtime::RuntimeApi, WestendExecutor>(config, jaeger_agent, Default::default(), None)?;
 		return Ok((Arc::new(Client::Westend(client)), backend, import_queue, task_manager))
 	}
 
 	let service::PartialComponents { client, backend, import_queue, task_manager, .. }
-		= new_partial::<polkadot_runtime::RuntimeApi, PolkadotExecutor>(config, jaeger_agent, None)?;
+		= new_partial::<polkadot_runtime::RuntimeApi, PolkadotExecutor>(config, jaeger_agent, Default::default(), None)?;
 	Ok((Arc::new(Client::Polkadot(client)), backend, import_queue, task_manager))
 }
 
@@ -1280,6 +1312,8 @@ pub fn build_full(
 	grandpa_pause: Option<(u32, u32)>,
 	disable_beefy: bool,
 	jaeger_agent: Option<std::net::SocketAddr>,
+	select_chain_config: relay_chain_selection::ChainSelectionConfig,
+	provisioner_timeout: Option<std::time::Duration>,
 	telemetry_worker_handle: Option<TelemetryWorkerHandle>,
 	overseer_gen: impl OverseerGen,
 ) -> Result<NewFull<Client>, Error> {
@@ -1291,6 +1325,8 @@ pub fn build_full(
 			grandpa_pause,
 			disable_beefy,
 			jaeger_agent,
+			select_chain_config,
+			provisioner_timeout,
 			telemetry_worker_handle,
 			None,
 			overseer_gen,
@@ -1305,6 +1341,8 @@ pub fn build_full(
 			grandpa_pause,
 			disable_beefy,
 			jaeger_agent,
+			select_chain_config,
+			provisioner_timeout,
 			telemetry_worker_handle,
 			None,
 			overseer_gen,
@@ -1319,6 +1357,8 @@ pub fn build_full(
 			grandpa_pause,
 			disable_beefy,
 			jaeger_agent,
+			select_chain_config,
+			provisioner_timeout,
 			telemetry_worker_handle,
 			None,
 			overseer_gen,
@@ -1331,6 +1371,8 @@ pub fn build_full(
 		grandpa_pause,
 		disable_beefy,
 		jaeger_agent,
+		select_chain_config,
+		provisioner_timeout,
 		telemetry_worker_handle,
 		None,
 		overseer_gen,
@@ -36,24 +36,105 @@
 #![cfg(feature = "full-node")]
 
 use polkadot_primitives::v1::{
-	Hash, BlockNumber, Block as PolkadotBlock, Header as PolkadotHeader,
+	Hash, BlockNumber, Block as PolkadotBlock, Header as PolkadotHeader, SessionIndex,
 };
-use polkadot_subsystem::messages::{ApprovalVotingMessage, HighestApprovedAncestorBlock, ChainSelectionMessage, DisputeCoordinatorMessage};
-use polkadot_node_subsystem_util::metrics::{self, prometheus};
-use futures::channel::oneshot;
+use polkadot_subsystem::messages::{
+	ApprovalVotingMessage, BlockDescription, HighestApprovedAncestorBlock, ChainSelectionMessage,
+	DisputeCoordinatorMessage,
+};
+use polkadot_node_subsystem_util::{metrics::{self, prometheus}, TimeoutExt};
+use futures::{channel::{mpsc, oneshot}, future::{self, Either, Shared}, FutureExt, StreamExt};
 use consensus_common::{Error as ConsensusError, SelectChain};
-use std::sync::Arc;
+use parity_scale_codec::{Compact, Decode, Encode};
+use sc_client_api::BlockchainEvents;
+use sp_core::storage::StorageKey;
+use sp_runtime::traits::Header as _;
+use std::sync::{Arc, Mutex, RwLock, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
 use polkadot_overseer::{AllMessages, Handle, OverseerHandle};
+use serde::{Deserialize, Serialize};
+use telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
 use super::{HeaderProvider, HeaderProviderProvider};
 
-/// The maximum amount of unfinalized blocks we are willing to allow due to approval checking
-/// or disputes.
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod test_helpers;
+
+#[cfg(test)]
+mod tests;
+
+/// The default maximum amount of unfinalized blocks we are willing to allow due to approval
+/// checking or disputes.
 ///
 /// This is a safety net that should be removed at some point in the future.
-const MAX_FINALITY_LAG: polkadot_primitives::v1::BlockNumber = 50;
+pub const DEFAULT_MAX_FINALITY_LAG: BlockNumber = 50;
+
+/// The default amount of time `finality_target` waits for each response of the
+/// chain selection, approval voting and dispute coordinator subsystems.
+///
+/// This is roughly the duration of a relay chain slot.
+pub const DEFAULT_FINALITY_TARGET_TIMEOUT: Duration = Duration::from_secs(6);
+
+/// The default amount of time the leaves returned by the chain selection subsystem
+/// are reused for, before the subsystem is asked again.
+pub const DEFAULT_LEAVES_CACHE_TTL: Duration = Duration::from_millis(500);
 
 const LOG_TARGET: &str = "parachain::chain-selection";
 
+/// The tunable parameters of the relay chain selection.
+///
+/// These are local to the node, which can read them from a JSON config file, where omitted
+/// fields fall back to their `DEFAULT_*` values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct ChainSelectionConfig {
+	/// The maximum amount of unfinalized blocks allowed due to approval checking or disputes.
+	pub max_finality_lag: BlockNumber,
+	/// The time in milliseconds `finality_target` waits for each subsystem response.
+	pub finality_target_timeout_ms: u64,
+	/// The time in milliseconds leaves are cached for.
+	pub leaves_cache_ttl_ms: u64,
+	/// The finality lag beyond which a warning is emitted, if any.
+	pub slow_finality_warn_threshold: Option<BlockNumber>,
+}
+
+impl Default for ChainSelectionConfig {
+	fn default() -> Self {
+		ChainSelectionConfig {
+			max_finality_lag: DEFAULT_MAX_FINALITY_LAG,
+			finality_target_timeout_ms: DEFAULT_FINALITY_TARGET_TIMEOUT.as_millis() as u64,
+			leaves_cache_ttl_ms: DEFAULT_LEAVES_CACHE_TTL.as_millis() as u64,
+			slow_finality_warn_threshold: None,
+		}
+	}
+}
+
+impl ChainSelectionConfig {
+	/// Read the config from the JSON file at the given path.
+	pub fn from_file(path: &std::path::Path) -> Result<Self, crate::Error> {
+		let file = std::fs::File::open(path)?;
+		Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+	}
+
+	/// The time `finality_target` waits for each subsystem response.
+	pub fn finality_target_timeout(&self) -> Duration {
+		Duration::from_millis(self.finality_target_timeout_ms)
+	}
+
+	/// The time leaves are cached for.
+	pub fn leaves_cache_ttl(&self) -> Duration {
+		Duration::from_millis(self.leaves_cache_ttl_ms)
+	}
+}
+
+/// The finality lags computed by a `finality_target` call, as reported to telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SelectChainTelemetry {
+	/// The number of blocks finality is behind the best leaf due to approval checking.
+	pub approval_checking_finality_lag: BlockNumber,
+	/// The number of blocks finality is behind the best leaf due to approval checking and disputes.
+	pub disputes_finality_lag: BlockNumber,
+}
+
 /// Prometheus metrics for chain-selection.
 #[derive(Debug, Default, Clone)]
 pub struct Metrics(Option<MetricsInner>);
@@ -62,6 +143,13 @@ pub struct Metrics(Option<MetricsInner>);
 struct MetricsInner {
 	approval_checking_finality_lag: prometheus::Gauge<prometheus::U64>,
 	disputes_finality_lag: prometheus::Gauge<prometheus::U64>,
+	disputes_finality_lag_histogram: prometheus::Histogram,
+	finality_target_timeouts: prometheus::CounterVec<prometheus::U64>,
+	forced_finality_targets: prometheus::Counter<prometheus::U64>,
+	empty_leaves: prometheus::Counter<prometheus::U64>,
+	finality_target_duration: prometheus::Histogram,
+	slow_finality_warnings: prometheus::Counter<prometheus::U64>,
+	overseer_msg_bytes: prometheus::Histogram,
 }
 
 impl metrics::Metrics for Metrics {
@@ -85,13 +173,100 @@ impl metrics::Metrics for Metrics {
 				)?,
 				registry,
 			)?,
+			disputes_finality_lag_histogram: prometheus::register(
+				prometheus::Histogram::with_opts(
+					prometheus::HistogramOpts::new(
+						"parachain_disputes_finality_lag_histogram",
+						"Distribution of how far behind the head of the chain the Disputes protocol wants to vote",
+					).buckets(vec![0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0]),
+				)?,
+				registry,
+			)?,
+			finality_target_timeouts: prometheus::register(
+				prometheus::CounterVec::new(
+					prometheus::Opts::new(
+						"parachain_finality_target_timeout_total",
+						"Number of subsystem requests of `finality_target` which timed out",
+					),
+					&["request"],
+				)?,
+				registry,
+			)?,
+			forced_finality_targets: prometheus::register(
+				prometheus::Counter::new(
+					"parachain_forced_finality_target_total",
+					"Number of `finality_target` calls which returned a forced finality target",
+				)?,
+				registry,
+			)?,
+			empty_leaves: prometheus::register(
+				prometheus::Counter::new(
+					"parachain_chain_selection_empty_leaves_total",
+					"Number of times chain selection returned no leaves",
+				)?,
+				registry,
+			)?,
+			finality_target_duration: prometheus::register(
+				prometheus::Histogram::with_opts(
+					prometheus::HistogramOpts::new(
+						"parachain_chain_selection_finality_target_duration_seconds",
+						"Time spent within `finality_target`",
+					).buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5]),
+				)?,
+				registry,
+			)?,
+			slow_finality_warnings: prometheus::register(
+				prometheus::Counter::new(
+					"parachain_slow_finality_warning_total",
+					"Number of `finality_target` calls with a finality lag beyond the slow finality warning threshold",
+				)?,
+				registry,
+			)?,
+			overseer_msg_bytes: prometheus::register(
+				prometheus::Histogram::with_opts(
+					prometheus::HistogramOpts::new(
+						"parachain_chain_selection_overseer_msg_bytes",
+						"Encoded size of the requests sent to the overseer by `finality_target`, excluding response channels",
+					).buckets(vec![32.0, 128.0, 512.0, 2048.0, 8192.0, 32768.0, 131072.0]),
+				)?,
+				registry,
+			)?,
 		};
 
 		Ok(Metrics(Some(metrics)))
 	}
 }
 
-impl Metrics {
+/// Metrics reported by the relay chain selection.
+///
+/// Decouples [`SelectRelayChain`] from the Prometheus registry.
+pub trait SelectChainMetricsT: Clone + Send + Sync {
+	/// Note how far behind the head of the chain the Approval Checking protocol wants to vote.
+	fn note_approval_checking_finality_lag(&self, lag: BlockNumber);
+
+	/// Note how far behind the head of the chain the Disputes protocol wants to vote.
+	fn note_disputes_finality_lag(&self, lag: BlockNumber);
+
+	/// Note that the given subsystem request of `finality_target` timed out.
+	fn on_finality_target_timeout(&self, request: &'static str);
+
+	/// Note that `finality_target` returned a forced finality target.
+	fn on_forced_finality_target(&self);
+
+	/// Note that chain selection returned no leaves.
+	fn on_empty_leaves(&self);
+
+	/// Provide a timer for `finality_target` which observes on drop.
+	fn time_finality_target(&self) -> Option<metrics::prometheus::prometheus::HistogramTimer>;
+
+	/// Note that `finality_target` computed a finality lag beyond the slow finality warning threshold.
+	fn on_slow_finality_warning(&self);
+
+	/// Note the encoded size of a request sent to the overseer by `finality_target`.
+	fn note_overseer_msg_bytes(&self, bytes: usize);
+}
+
+impl SelectChainMetricsT for Metrics {
 	fn note_approval_checking_finality_lag(&self, lag: BlockNumber) {
 		if let Some(ref metrics) = self.0 {
 			metrics.approval_checking_finality_lag.set(lag as _);
@@ -101,8 +276,67 @@ impl Metrics {
 	fn note_disputes_finality_lag(&self, lag: BlockNumber) {
 		if let Some(ref metrics) = self.0 {
 			metrics.disputes_finality_lag.set(lag as _);
+			metrics.disputes_finality_lag_histogram.observe(lag as _);
+		}
+	}
+
+	fn on_finality_target_timeout(&self, request: &'static str) {
+		if let Some(ref metrics) = self.0 {
+			metrics.finality_target_timeouts.with_label_values(&[request]).inc();
+		}
+	}
+
+	fn on_forced_finality_target(&self) {
+		if let Some(ref metrics) = self.0 {
+			metrics.forced_finality_targets.inc();
+		}
+	}
+
+	fn on_empty_leaves(&self) {
+		if let Some(ref metrics) = self.0 {
+			metrics.empty_leaves.inc();
+		}
+	}
+
+	fn time_finality_target(&self) -> Option<metrics::prometheus::prometheus::HistogramTimer> {
+		self.0.as_ref().map(|metrics| metrics.finality_target_duration.start_timer())
+	}
+
+	fn on_slow_finality_warning(&self) {
+		if let Some(ref metrics) = self.0 {
+			metrics.slow_finality_warnings.inc();
 		}
 	}
+
+	fn note_overseer_msg_bytes(&self, bytes: usize) {
+		if let Some(ref metrics) = self.0 {
+			metrics.overseer_msg_bytes.observe(bytes as _);
+		}
+	}
+}
+
+/// Metrics which are discarded, e.g. for testing purposes.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl SelectChainMetricsT for NoopMetrics {
+	fn note_approval_checking_finality_lag(&self, _lag: BlockNumber) {}
+
+	fn note_disputes_finality_lag(&self, _lag: BlockNumber) {}
+
+	fn on_finality_target_timeout(&self, _request: &'static str) {}
+
+	fn on_forced_finality_target(&self) {}
+
+	fn on_empty_leaves(&self) {}
+
+	fn time_finality_target(&self) -> Option<metrics::prometheus::prometheus::HistogramTimer> {
+		None
+	}
+
+	fn on_slow_finality_warning(&self) {}
+
+	fn note_overseer_msg_bytes(&self, _bytes: usize) {}
 }
 
 /// A chain-selection implementation which provides safety for relay chains.
@@ -117,6 +351,7 @@ pub struct SelectRelayChainWithFallback<
 	selection: SelectRelayChain<
 		B,
 		Handle,
+		Metrics,
 	>,
 }
 
@@ -126,6 +361,7 @@ where
 	SelectRelayChain<
 		B,
 		Handle,
+		Metrics,
 	>: Clone,
 {
 	fn clone(&self) -> Self {
@@ -142,17 +378,55 @@ where
 	B: sc_client_api::Backend<PolkadotBlock> + 'static,
 {
 	/// Create a new [`SelectRelayChainWithFallback`] wrapping the given chain backend
-	/// and a handle to the overseer.
-	pub fn new(backend: Arc<B>, overseer: Handle, metrics: Metrics) -> Self {
+	/// and a handle to the overseer, tuned by the given [`ChainSelectionConfig`].
+	pub fn new(
+		backend: Arc<B>,
+		overseer: Handle,
+		metrics: Metrics,
+		config: ChainSelectionConfig,
+	) -> Self {
+		let mut selection = SelectRelayChain::new(
+			backend.clone(),
+			overseer,
+			metrics,
+			config.finality_target_timeout(),
+			config.max_finality_lag,
+			config.leaves_cache_ttl(),
+		);
+		selection.slow_finality_warn_threshold = config.slow_finality_warn_threshold;
+
 		SelectRelayChainWithFallback {
-			fallback: sc_consensus::LongestChain::new(backend.clone()),
-			selection: SelectRelayChain::new(
-				backend,
-				overseer,
-				metrics,
-			),
+			fallback: sc_consensus::LongestChain::new(backend),
+			selection,
 		}
 	}
+
+	/// Create a new [`SelectRelayChainWithFallback`] connected to an already running overseer,
+	/// tuned by the given [`ChainSelectionConfig`].
+	///
+	/// This is equivalent to creating it with a disconnected handle and calling
+	/// [`connect_to_overseer`](Self::connect_to_overseer) right away.
+	pub fn with_overseer_handle(
+		backend: Arc<B>,
+		handle: OverseerHandle,
+		metrics: Metrics,
+		config: ChainSelectionConfig,
+	) -> Self {
+		Self::new(backend, Handle::Connected(handle), metrics, config)
+	}
+
+	/// Create a new [`SelectRelayChainWithFallback`] tuned by the given [`ChainSelectionConfig`],
+	/// registering its metrics in the given registry, if any.
+	pub fn new_with_metrics(
+		backend: Arc<B>,
+		overseer: Handle,
+		registry: Option<&prometheus::Registry>,
+		config: ChainSelectionConfig,
+	) -> Result<Self, prometheus::PrometheusError> {
+		use metrics::Metrics as _;
+
+		Ok(Self::new(backend, overseer, Metrics::register(registry)?, config))
+	}
 }
 
 impl<B> SelectRelayChainWithFallback<B>
@@ -161,11 +435,162 @@ where
 {
 	/// Given an overseer handle, this connects the [`SelectRelayChainWithFallback`]'s
 	/// internal handle and its clones to the same overseer.
+	///
+	/// This may be called again to reconnect to a restarted overseer. Until then, the
+	/// fallback is used.
 	pub fn connect_to_overseer(
 		&mut self,
 		handle: OverseerHandle,
 	) {
-		self.selection.overseer.connect_to_overseer(handle);
+		if let Ok(mut overseer) = self.selection.overseer.write() {
+			match overseer.as_mut() {
+				Some(overseer) => overseer.connect_to_overseer(handle),
+				None => *overseer = Some(Handle::Connected(handle)),
+			}
+		}
+	}
+
+	/// Report the finality lags to telemetry from now on.
+	///
+	/// See [`SelectRelayChain::set_telemetry`].
+	pub fn set_telemetry(&mut self, telemetry: TelemetryHandle) {
+		self.selection.set_telemetry(telemetry)
+	}
+
+	/// Disconnect from the overseer, e.g. during a controlled shutdown of the node.
+	///
+	/// See [`SelectRelayChain::disconnect`].
+	pub fn disconnect(&self) {
+		self.selection.disconnect()
+	}
+
+	/// Replace the overseer handle, undoing a [`disconnect`](Self::disconnect).
+	///
+	/// See [`SelectRelayChain::reconnect`].
+	pub fn reconnect(&self, handle: OverseerHandle) {
+		self.selection.reconnect(Handle::Connected(handle))
+	}
+
+	/// Force `finality_target` to return the given hash until
+	/// [`clear_forced_finality_target`](Self::clear_forced_finality_target) is called.
+	///
+	/// See [`SelectRelayChain::force_finality_target`].
+	pub fn force_finality_target(&self, hash: Hash) {
+		self.selection.force_finality_target(hash)
+	}
+
+	/// Clear a finality target previously set by
+	/// [`force_finality_target`](Self::force_finality_target).
+	pub fn clear_forced_finality_target(&self) {
+		self.selection.clear_forced_finality_target()
+	}
+
+	/// Ask the dispute coordinator shard behind the given handle about the undisputed chain
+	/// as well.
+	///
+	/// See [`SelectRelayChain::register_dispute_coordinator`].
+	pub fn register_dispute_coordinator(&self, handle: OverseerHandle) {
+		self.selection.register_dispute_coordinator(Handle::Connected(handle))
+	}
+
+	/// Subscribe to the finality lags computed by `finality_target`.
+	///
+	/// See [`SelectRelayChain::subscribe_finality_lag`].
+	pub fn subscribe_finality_lag(&self, tx: mpsc::Sender<(BlockNumber, BlockNumber)>) {
+		self.selection.subscribe_finality_lag(tx)
+	}
+
+	/// Cancel all pending and future subsystem requests of `finality_target`.
+	///
+	/// See [`SelectRelayChain::shutdown`].
+	pub fn shutdown(&self) {
+		self.selection.shutdown()
+	}
+
+	/// Get the latest approved and undisputed block at most `max_backtrack` blocks behind the
+	/// best leaf, or the best block of the fallback if disconnected from the overseer.
+	///
+	/// See [`SelectRelayChain::best_chain_without_disputes`].
+	pub async fn best_chain_without_disputes(
+		&self,
+		max_backtrack: u32,
+	) -> Result<PolkadotHeader, ConsensusError> {
+		if !self.selection.reconnect_if_needed() {
+			return self.fallback.best_chain().await
+		}
+
+		self.selection.best_chain_without_disputes(max_backtrack).await
+	}
+
+	/// Get the latest approved and undisputed ancestor of `target`, or `target` itself if
+	/// disconnected from the overseer.
+	///
+	/// See [`SelectRelayChain::disputes_chain_head`].
+	pub async fn disputes_chain_head(&self, target: Hash) -> Result<Option<Hash>, ConsensusError> {
+		if !self.selection.reconnect_if_needed() {
+			return Ok(Some(target))
+		}
+
+		self.selection.disputes_chain_head(target).await
+	}
+
+	/// Note the current session, clearing all caches if it changed.
+	///
+	/// See [`SelectRelayChain::note_session`].
+	pub fn note_session(&self, session: SessionIndex) {
+		self.selection.note_session(session)
+	}
+}
+
+/// The storage key of the current session index of the session pallet.
+fn current_session_index_key() -> StorageKey {
+	let mut key = sp_core::twox_128(b"Session").to_vec();
+	key.extend_from_slice(&sp_core::twox_128(b"CurrentIndex"));
+	StorageKey(key)
+}
+
+/// Note every change of the current session index in the storage of imported blocks with
+/// the given chain selection, until the client stops sending storage change notifications.
+pub async fn forward_session_changes<B, C>(
+	client: Arc<C>,
+	select_chain: SelectRelayChainWithFallback<B>,
+)
+where
+	B: sc_client_api::Backend<PolkadotBlock> + 'static,
+	C: BlockchainEvents<PolkadotBlock>,
+{
+	let mut changes = match client.storage_changes_notification_stream(
+		Some(&[current_session_index_key()]),
+		None,
+	) {
+		Ok(changes) => changes,
+		Err(err) => {
+			tracing::warn!(target: LOG_TARGET, ?err, "Failed to subscribe to session changes");
+			return
+		}
+	};
+
+	while let Some((_, change_set)) = changes.next().await {
+		let sessions = change_set.iter()
+			.filter_map(|(_, _, value)| value)
+			.filter_map(|value| SessionIndex::decode(&mut &value.0[..]).ok());
+
+		for session in sessions {
+			select_chain.note_session(session);
+		}
+	}
+}
+
+impl<B> polkadot_rpc::parachain::ChainSelectionHealthProvider for SelectRelayChainWithFallback<B>
+where
+	B: sc_client_api::Backend<PolkadotBlock> + 'static,
+{
+	fn chain_selection_health(&self) -> polkadot_rpc::parachain::ChainSelectionHealth {
+		polkadot_rpc::parachain::ChainSelectionHealth {
+			is_connected: self.selection.is_connected(),
+			leaves_count: self.selection.known_leaves_set().len(),
+			last_finality_lag: self.selection.last_finality_lag(),
+		}
 	}
 }
 
@@ -176,7 +601,7 @@ where
 	B: sc_client_api::Backend<PolkadotBlock> + 'static,
 {
 	async fn leaves(&self) -> Result<Vec<Hash>, ConsensusError> {
-		if self.selection.overseer.is_disconnected() {
+		if !self.selection.reconnect_if_needed() {
 			return self.fallback.leaves().await
 		}
 
@@ -184,7 +609,7 @@ where
 	}
 
 	async fn best_chain(&self) -> Result<PolkadotHeader, ConsensusError> {
-		if self.selection.overseer.is_disconnected() {
+		if !self.selection.reconnect_if_needed() {
 			return self.fallback.best_chain().await
 		}
 		self.selection.best_chain().await
@@ -195,7 +620,12 @@ where
 		target_hash: Hash,
 		maybe_max_number: Option<BlockNumber>,
 	) -> Result<Option<Hash>, ConsensusError> {
-		if self.selection.overseer.is_disconnected() {
+		if !self.selection.reconnect_if_needed() {
+			// When connected, the selection checks for a forced finality target itself.
+			if let Some(forced) = self.selection.forced_finality_target(target_hash, maybe_max_number) {
+				return Ok(Some(forced))
+			}
+
 			return self.fallback.finality_target(target_hash, maybe_max_number).await
 		}
 		self.selection.finality_target(target_hash, maybe_max_number).await
@@ -203,171 +633,800 @@ where
 }
 
 
-/// A chain-selection implementation which provides safety for relay chains
-/// but does not handle situations where the overseer is not yet connected.
-pub struct SelectRelayChain<B, OH> {
-	backend: Arc<B>,
+/// Marks a required field of a [`SelectRelayChainBuilder`] which was not set yet.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Unset;
+
+/// A builder for [`SelectRelayChain`].
+///
+/// The backend and the overseer handle are required: `build` is only available once both
+/// were set. The metrics default to [`NoopMetrics`], the timeouts and limits to their
+/// `DEFAULT_*` values and there is no slow finality warning threshold.
+pub struct SelectRelayChainBuilder<B, OH, M> {
+	backend: B,
 	overseer: OH,
-	metrics: Metrics,
+	metrics: M,
+	finality_target_timeout: Duration,
+	max_finality_lag: BlockNumber,
+	leaves_cache_ttl: Duration,
+	slow_finality_warn_threshold: Option<BlockNumber>,
+	telemetry: Option<TelemetryHandle>,
 }
 
-impl<B, OH> SelectRelayChain<B, OH>
-where
-	B: HeaderProviderProvider<PolkadotBlock>,
-	OH: OverseerHandleT,
-{
-	/// Create a new [`SelectRelayChain`] wrapping the given chain backend
-	/// and a handle to the overseer.
-	pub fn new(backend: Arc<B>, overseer: OH, metrics: Metrics) -> Self {
-		SelectRelayChain {
-			backend,
-			overseer,
-			metrics,
+impl Default for SelectRelayChainBuilder<Unset, Unset, NoopMetrics> {
+	fn default() -> Self {
+		SelectRelayChainBuilder {
+			backend: Unset,
+			overseer: Unset,
+			metrics: NoopMetrics,
+			finality_target_timeout: DEFAULT_FINALITY_TARGET_TIMEOUT,
+			max_finality_lag: DEFAULT_MAX_FINALITY_LAG,
+			leaves_cache_ttl: DEFAULT_LEAVES_CACHE_TTL,
+			slow_finality_warn_threshold: None,
+			telemetry: None,
 		}
 	}
+}
 
-	fn block_header(&self, hash: Hash) -> Result<PolkadotHeader, ConsensusError> {
-		match HeaderProvider::header(self.backend.header_provider(), hash) {
-			Ok(Some(header)) => Ok(header),
-			Ok(None) => Err(ConsensusError::ChainLookup(format!(
-				"Missing header with hash {:?}",
-				hash,
-			))),
-			Err(e) => Err(ConsensusError::ChainLookup(format!(
-				"Lookup failed for header with hash {:?}: {:?}",
-				hash,
-				e,
-			))),
+impl<B, OH, M> SelectRelayChainBuilder<B, OH, M> {
+	/// Set the chain backend.
+	pub fn backend<NewB>(self, backend: Arc<NewB>) -> SelectRelayChainBuilder<Arc<NewB>, OH, M> {
+		SelectRelayChainBuilder {
+			backend,
+			overseer: self.overseer,
+			metrics: self.metrics,
+			finality_target_timeout: self.finality_target_timeout,
+			max_finality_lag: self.max_finality_lag,
+			leaves_cache_ttl: self.leaves_cache_ttl,
+			slow_finality_warn_threshold: self.slow_finality_warn_threshold,
+			telemetry: self.telemetry,
 		}
 	}
 
-	fn block_number(&self, hash: Hash) -> Result<BlockNumber, ConsensusError> {
-		match HeaderProvider::number(self.backend.header_provider(), hash) {
-			Ok(Some(number)) => Ok(number),
-			Ok(None) => Err(ConsensusError::ChainLookup(format!(
-				"Missing number with hash {:?}",
-				hash,
-			))),
-			Err(e) => Err(ConsensusError::ChainLookup(format!(
-				"Lookup failed for number with hash {:?}: {:?}",
-				hash,
-				e,
-			))),
+	/// Set the handle to the overseer.
+	pub fn overseer<NewOH>(self, overseer: NewOH) -> SelectRelayChainBuilder<B, NewOH, M> {
+		SelectRelayChainBuilder {
+			backend: self.backend,
+			overseer,
+			metrics: self.metrics,
+			finality_target_timeout: self.finality_target_timeout,
+			max_finality_lag: self.max_finality_lag,
+			leaves_cache_ttl: self.leaves_cache_ttl,
+			slow_finality_warn_threshold: self.slow_finality_warn_threshold,
+			telemetry: self.telemetry,
 		}
 	}
-}
 
-impl<B, OH> Clone for SelectRelayChain<B, OH>
-where
-	B: HeaderProviderProvider<PolkadotBlock> + Send + Sync,
-	OH: OverseerHandleT,
-{
-	fn clone(&self) -> Self {
-		SelectRelayChain {
-			backend: self.backend.clone(),
-			overseer: self.overseer.clone(),
-			metrics: self.metrics.clone(),
+	/// Set the metrics.
+	pub fn metrics<NewM>(self, metrics: NewM) -> SelectRelayChainBuilder<B, OH, NewM> {
+		SelectRelayChainBuilder {
+			backend: self.backend,
+			overseer: self.overseer,
+			metrics,
+			finality_target_timeout: self.finality_target_timeout,
+			max_finality_lag: self.max_finality_lag,
+			leaves_cache_ttl: self.leaves_cache_ttl,
+			slow_finality_warn_threshold: self.slow_finality_warn_threshold,
+			telemetry: self.telemetry,
 		}
 	}
-}
 
-#[derive(thiserror::Error, Debug)]
-enum Error {
-	// A request to the subsystem was canceled.
-	#[error("Overseer is disconnected from Chain Selection")]
-	OverseerDisconnected(oneshot::Canceled),
-	/// Chain selection returned empty leaves.
-	#[error("ChainSelection returned no leaves")]
-	EmptyLeaves,
-}
+	/// Set the time `finality_target` waits for each subsystem response.
+	pub fn finality_target_timeout(mut self, finality_target_timeout: Duration) -> Self {
+		self.finality_target_timeout = finality_target_timeout;
+		self
+	}
 
+	/// Set the maximum amount of unfinalized blocks allowed due to approval checking or disputes.
+	pub fn max_finality_lag(mut self, max_finality_lag: BlockNumber) -> Self {
+		self.max_finality_lag = max_finality_lag;
+		self
+	}
 
-/// Decoupling trait for the overseer handle.
-///
-/// Required for testing purposes.
-#[async_trait::async_trait]
-pub trait OverseerHandleT: Clone + Send + Sync {
-	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str);
-}
+	/// Set the amount of time leaves are cached for.
+	pub fn leaves_cache_ttl(mut self, leaves_cache_ttl: Duration) -> Self {
+		self.leaves_cache_ttl = leaves_cache_ttl;
+		self
+	}
 
-#[async_trait::async_trait]
-impl OverseerHandleT for Handle {
-	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) {
-		Handle::send_msg(self, msg, origin).await
+	/// Warn about finality lags beyond the given threshold, ahead of the maximum finality lag.
+	pub fn slow_finality_warn_threshold(mut self, threshold: BlockNumber) -> Self {
+		self.slow_finality_warn_threshold = Some(threshold);
+		self
 	}
-}
 
+	/// Report the finality lags to telemetry.
+	pub fn telemetry(mut self, telemetry: TelemetryHandle) -> Self {
+		self.telemetry = Some(telemetry);
+		self
+	}
+}
 
-#[async_trait::async_trait]
-impl<B, OH> SelectChain<PolkadotBlock> for SelectRelayChain<B, OH>
+impl<B, OH, M> SelectRelayChainBuilder<Arc<B>, OH, M>
 where
 	B: HeaderProviderProvider<PolkadotBlock>,
 	OH: OverseerHandleT,
+	M: SelectChainMetricsT,
 {
-	/// Get all leaves of the chain, i.e. block hashes that are suitable to
-	/// build upon and have no suitable children.
-	async fn leaves(&self) -> Result<Vec<Hash>, ConsensusError> {
-		let (tx, rx) = oneshot::channel();
-
-		self.overseer
-			.clone()
-			.send_msg(
-				ChainSelectionMessage::Leaves(tx),
-				std::any::type_name::<Self>(),
-			).await;
-
-		rx.await
-			.map_err(Error::OverseerDisconnected)
-			.map_err(|e| ConsensusError::Other(Box::new(e)))
+	/// Build the [`SelectRelayChain`].
+	pub fn build(self) -> SelectRelayChain<B, OH, M> {
+		let mut select_relay_chain = SelectRelayChain::new(
+			self.backend,
+			self.overseer,
+			self.metrics,
+			self.finality_target_timeout,
+			self.max_finality_lag,
+			self.leaves_cache_ttl,
+		);
+		select_relay_chain.slow_finality_warn_threshold = self.slow_finality_warn_threshold;
+		select_relay_chain.telemetry = self.telemetry;
+		select_relay_chain
 	}
+}
 
-	/// Among all leaves, pick the one which is the best chain to build upon.
-	async fn best_chain(&self) -> Result<PolkadotHeader, ConsensusError> {
-		// The Chain Selection subsystem is supposed to treat the finalized
-		// block as the best leaf in the case that there are no viable
-		// leaves, so this should not happen in practice.
-		let best_leaf = self.leaves()
-			.await?
-			.first()
-			.ok_or_else(|| ConsensusError::Other(Box::new(Error::EmptyLeaves)))?
-			.clone();
-
+/// A chain-selection implementation which provides safety for relay chains
+/// but does not handle situations where the overseer is not yet connected.
+pub struct SelectRelayChain<B, OH, M> {
+	backend: Arc<B>,
+	// The handle to the overseer, shared among all clones. `None` once disconnected.
+	overseer: Arc<RwLock<Option<OH>>>,
+	// Handles to additional dispute coordinator shards, asked alongside the overseer.
+	dispute_coordinators: Arc<RwLock<Vec<OH>>>,
+	metrics: M,
+	finality_target_timeout: Duration,
+	max_finality_lag: BlockNumber,
+	// The finality lag beyond which a warning is emitted, if any.
+	slow_finality_warn_threshold: Option<BlockNumber>,
+	// The most recently fetched leaves and the instant they were fetched at.
+	leaves_cache: Arc<Mutex<Option<(Vec<Hash>, Instant)>>>,
+	leaves_cache_ttl: Duration,
+	// A finality target set by an operator, overriding the chain selection rules.
+	forced_finality_target: Arc<RwLock<Option<Hash>>>,
+	// A subscriber to the approval checking and disputes finality lags.
+	finality_lag_subscriber: Arc<Mutex<Option<mpsc::Sender<(BlockNumber, BlockNumber)>>>>,
+	// Whether the overseer was running when last checked.
+	connected: Arc<AtomicBool>,
+	// The last noted session, if any.
+	session: Arc<Mutex<Option<SessionIndex>>>,
+	// The disputes finality lag computed by the last `finality_target` call.
+	last_finality_lag: Arc<Mutex<Option<BlockNumber>>>,
+	// Resolves once `shutdown` is called on any clone, canceling pending subsystem requests.
+	shutdown: Shared<oneshot::Receiver<()>>,
+	shutdown_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+	// The telemetry the finality lags are reported to, if any.
+	telemetry: Option<TelemetryHandle>,
+}
 
-		self.block_header(best_leaf)
+impl<B, OH, M> SelectRelayChain<B, OH, M>
+where
+	B: HeaderProviderProvider<PolkadotBlock>,
+	OH: OverseerHandleT,
+	M: SelectChainMetricsT,
+{
+	/// Create a new [`SelectRelayChain`] wrapping the given chain backend
+	/// and a handle to the overseer.
+	///
+	/// `finality_target_timeout` bounds the time `finality_target` waits for each
+	/// subsystem response, `max_finality_lag` is the maximum amount of unfinalized
+	/// blocks allowed due to approval checking or disputes and `leaves_cache_ttl`
+	/// is the amount of time leaves are cached for.
+	pub fn new(
+		backend: Arc<B>,
+		overseer: OH,
+		metrics: M,
+		finality_target_timeout: Duration,
+		max_finality_lag: BlockNumber,
+		leaves_cache_ttl: Duration,
+	) -> Self {
+		let (shutdown_tx, shutdown) = oneshot::channel();
+		SelectRelayChain {
+			backend,
+			overseer: Arc::new(RwLock::new(Some(overseer))),
+			dispute_coordinators: Arc::new(RwLock::new(Vec::new())),
+			metrics,
+			finality_target_timeout,
+			max_finality_lag,
+			slow_finality_warn_threshold: None,
+			leaves_cache: Arc::new(Mutex::new(None)),
+			leaves_cache_ttl,
+			forced_finality_target: Arc::new(RwLock::new(None)),
+			finality_lag_subscriber: Arc::new(Mutex::new(None)),
+			connected: Arc::new(AtomicBool::new(false)),
+			session: Arc::new(Mutex::new(None)),
+			last_finality_lag: Arc::new(Mutex::new(None)),
+			shutdown: shutdown.shared(),
+			shutdown_tx: Arc::new(Mutex::new(Some(shutdown_tx))),
+			telemetry: None,
+		}
 	}
 
-	/// Get the best descendant of `target_hash` that we should attempt to
-	/// finalize next, if any. It is valid to return the `target_hash` if
-	/// no better block exists.
+	/// Force `finality_target` to return the given hash until
+	/// [`clear_forced_finality_target`](Self::clear_forced_finality_target) is called, whenever
+	/// it is a valid vote for the requested target.
 	///
-	/// This will search all leaves to find the best one containing the
-	/// given target hash, and then constrain to the given block number.
+	/// This is meant for recovery tooling, e.g. when the network is frozen due to
+	/// disputes and operators need to vote on a known-good block. The override is
+	/// shared among all clones.
+	pub fn force_finality_target(&self, hash: Hash) {
+		tracing::warn!(target: LOG_TARGET, ?hash, "Forcing the finality target");
+
+		if let Ok(mut forced) = self.forced_finality_target.write() {
+			*forced = Some(hash);
+		}
+	}
+
+	/// Clear a finality target previously set by
+	/// [`force_finality_target`](Self::force_finality_target).
+	pub fn clear_forced_finality_target(&self) {
+		if let Ok(mut forced) = self.forced_finality_target.write() {
+			if forced.take().is_some() {
+				tracing::info!(target: LOG_TARGET, "Cleared the forced finality target");
+			}
+		}
+	}
+
+	/// Send the approval checking and disputes finality lags computed by every subsequent
+	/// `finality_target` call to the given channel, in that order.
 	///
-	/// It will also constrain the chain to only chains which are fully
-	/// approved, and chains which contain no disputes.
-	async fn finality_target(
+	/// This replaces any previous subscription and is shared among all clones. Lags are
+	/// dropped rather than waited for if the channel is full, and the subscription is
+	/// dropped once the receiver is closed.
+	pub fn subscribe_finality_lag(&self, tx: mpsc::Sender<(BlockNumber, BlockNumber)>) {
+		if let Ok(mut subscriber) = self.finality_lag_subscriber.lock() {
+			*subscriber = Some(tx);
+		}
+	}
+
+	/// Cancel all pending and future subsystem requests of `finality_target`, of this
+	/// instance and all its clones.
+	///
+	/// Canceled calls return an error rather than waiting for their response or timeout.
+	pub fn shutdown(&self) {
+		if let Ok(mut shutdown_tx) = self.shutdown_tx.lock() {
+			if let Some(shutdown_tx) = shutdown_tx.take() {
+				tracing::debug!(target: LOG_TARGET, "Shutting down chain selection");
+				let _ = shutdown_tx.send(());
+			}
+		}
+	}
+
+	/// Note the current session, clearing the cached leaves and finality lag of this instance
+	/// and all its clones if it differs from the last noted session.
+	///
+	/// Cached state of the previous session must not be used once the validator set changed.
+	pub fn note_session(&self, session: SessionIndex) {
+		let previous = match self.session.lock() {
+			Ok(mut last_session) => last_session.replace(session),
+			Err(_) => return,
+		};
+
+		if previous != Some(session) {
+			tracing::info!(
+				target: LOG_TARGET,
+				session,
+				?previous,
+				"New session, clearing the chain selection caches",
+			);
+			self.clear_caches();
+		}
+	}
+
+	/// Clear the cached leaves and the last finality lag.
+	fn clear_caches(&self) {
+		if let Ok(mut cache) = self.leaves_cache.lock() {
+			*cache = None;
+		}
+		if let Ok(mut last_finality_lag) = self.last_finality_lag.lock() {
+			*last_finality_lag = None;
+		}
+	}
+
+	/// The disputes finality lag computed by the last `finality_target` call, if any.
+	pub fn last_finality_lag(&self) -> Option<BlockNumber> {
+		self.last_finality_lag.lock().ok().and_then(|lag| *lag)
+	}
+
+	/// Report the finality lags to telemetry from now on, for this instance and clones created
+	/// afterwards.
+	pub fn set_telemetry(&mut self, telemetry: TelemetryHandle) {
+		self.telemetry = Some(telemetry);
+	}
+
+	/// Report the finality lags to telemetry, if any.
+	fn report_finality_lag(&self, approval_lag: BlockNumber, disputes_lag: BlockNumber) {
+		telemetry!(
+			self.telemetry;
+			SUBSTRATE_INFO;
+			"parachain.chain_selection.finality_lag";
+			"lags" => SelectChainTelemetry {
+				approval_checking_finality_lag: approval_lag,
+				disputes_finality_lag: disputes_lag,
+			}
+		);
+	}
+
+	/// Note the finality lags and notify the finality lag subscriber, if any.
+	fn notify_finality_lag(&self, approval_lag: BlockNumber, disputes_lag: BlockNumber) {
+		if let Ok(mut last_finality_lag) = self.last_finality_lag.lock() {
+			*last_finality_lag = Some(disputes_lag);
+		}
+
+		if let Ok(mut subscriber) = self.finality_lag_subscriber.lock() {
+			let disconnected = match subscriber.as_mut() {
+				Some(tx) => tx.try_send((approval_lag, disputes_lag))
+					.map_or_else(|e| e.is_disconnected(), |_| false),
+				None => false,
+			};
+
+			if disconnected {
+				tracing::debug!(target: LOG_TARGET, "Finality lag subscriber disconnected");
+				*subscriber = None;
+			}
+		}
+	}
+
+	/// Disconnect from the overseer, e.g. during a controlled shutdown of the node.
+	///
+	/// The overseer handle is dropped by this instance and all its clones, so all further
+	/// subsystem requests fail as if the overseer had exited, until [`reconnect`](Self::reconnect)
+	/// is called. Pending requests are not affected.
+	pub fn disconnect(&self) {
+		if let Ok(mut overseer) = self.overseer.write() {
+			if overseer.take().is_some() {
+				tracing::info!(target: LOG_TARGET, "Disconnecting chain selection from the overseer");
+			}
+		}
+	}
+
+	/// Replace the overseer handle of this instance and all its clones, undoing a
+	/// [`disconnect`](Self::disconnect).
+	pub fn reconnect(&self, handle: OH) {
+		if let Ok(mut overseer) = self.overseer.write() {
+			tracing::info!(target: LOG_TARGET, "Reconnecting chain selection to the overseer");
+			*overseer = Some(handle);
+		}
+	}
+
+	/// A clone of the overseer handle, or `None` if disconnected.
+	fn overseer(&self) -> Option<OH> {
+		self.overseer.read().ok().and_then(|overseer| overseer.clone())
+	}
+
+	/// Ask the dispute coordinator shard behind the given handle about the undisputed chain
+	/// as well, for this instance and all its clones.
+	///
+	/// The most conservative response of all dispute coordinators is used.
+	pub fn register_dispute_coordinator(&self, handle: OH) {
+		if let Ok(mut dispute_coordinators) = self.dispute_coordinators.write() {
+			tracing::info!(target: LOG_TARGET, "Registering an additional dispute coordinator");
+			dispute_coordinators.push(handle);
+		}
+	}
+
+	/// Clones of the handles to all dispute coordinators: the overseer, unless disconnected,
+	/// and all registered dispute coordinator shards.
+	fn dispute_coordinators(&self) -> Vec<OH> {
+		let shards = self.dispute_coordinators.read()
+			.map(|shards| shards.clone())
+			.unwrap_or_default();
+
+		self.overseer().into_iter().chain(shards).collect()
+	}
+
+	/// Send a message to the overseer.
+	///
+	/// The message is dropped if disconnected, which drops its response channel, so the
+	/// request fails as if the overseer had exited.
+	async fn send_msg<Msg: Send + Into<AllMessages>>(&self, msg: Msg) {
+		match self.overseer() {
+			Some(mut overseer) => overseer.send_msg(msg, std::any::type_name::<Self>()).await,
+			None => tracing::debug!(target: LOG_TARGET, "Dropping a request, chain selection is disconnected"),
+		}
+	}
+
+	/// Warn if the finality lag exceeds the slow finality warning threshold, if any.
+	///
+	/// Unlike the maximum finality lag, this does not constrain the finality target.
+	fn emit_slow_finality_warning(&self, lag: BlockNumber) {
+		match self.slow_finality_warn_threshold {
+			Some(threshold) if lag > threshold => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					lag,
+					threshold,
+					max_finality_lag = self.max_finality_lag,
+					"Finality is falling behind",
+				);
+				self.metrics.on_slow_finality_warning();
+			}
+			_ => {}
+		}
+	}
+
+	/// Whether the overseer was running when last checked by `leaves`, `best_chain` or
+	/// `finality_target`.
+	pub fn is_connected(&self) -> bool {
+		self.connected.load(Ordering::Relaxed)
+	}
+
+	/// Re-check whether the overseer is running, returning whether it is.
+	///
+	/// The overseer handle is cloned for every request, and the clones connect to whichever
+	/// overseer the handle is connected to at that time. A restarted overseer connected
+	/// through [`SelectRelayChainWithFallback::connect_to_overseer`] is therefore picked up
+	/// by the next request, and this only needs to track the connection state.
+	fn reconnect_if_needed(&self) -> bool {
+		let connected = self.overseer().map_or(false, |overseer| overseer.is_connected());
+		if self.connected.swap(connected, Ordering::Relaxed) != connected {
+			if connected {
+				tracing::info!(target: LOG_TARGET, "Chain selection is connected to the overseer");
+			} else {
+				tracing::warn!(target: LOG_TARGET, "Chain selection lost the connection to the overseer");
+			}
+		}
+
+		connected
+	}
+
+	/// The forced finality target, if any and if it is a valid vote for `target_hash`, i.e. a
+	/// descendant of it or the block itself, not above `maybe_max_number`.
+	///
+	/// Every returned target is logged and noted in the metrics, as it is used
+	/// for voting. Invalid forced targets are ignored with a warning.
+	fn forced_finality_target(
 		&self,
 		target_hash: Hash,
 		maybe_max_number: Option<BlockNumber>,
-	) -> Result<Option<Hash>, ConsensusError> {
-		let mut overseer = self.overseer.clone();
+	) -> Option<Hash> {
+		let forced = self.forced_finality_target.read().ok().and_then(|forced| *forced)?;
 
-		let subchain_head = {
-			let (tx, rx) = oneshot::channel();
-			overseer.send_msg(
-				ChainSelectionMessage::BestLeafContaining(target_hash, tx),
-				std::any::type_name::<Self>(),
-			).await;
+		let is_valid_vote = || -> Result<bool, ConsensusError> {
+			let forced_header = self.block_header(forced)?;
+			if maybe_max_number.map_or(false, |max_number| forced_header.number > max_number) {
+				return Ok(false)
+			}
 
-			let best = rx.await
-				.map_err(Error::OverseerDisconnected)
-				.map_err(|e| ConsensusError::Other(Box::new(e)))?;
+			let target_number = self.block_number(target_hash)?;
+			if forced_header.number < target_number {
+				return Ok(false)
+			}
 
-			match best {
-				// No viable leaves containing the block.
-				None => return Ok(Some(target_hash)),
-				Some(best) => best,
+			let (ancestor, _) = crate::grandpa_support::walk_backwards_to_target_block(
+				self.backend.header_provider(),
+				target_number,
+				&forced_header,
+			).map_err(|e| ConsensusError::ChainLookup(format!("{:?}", e)))?;
+			Ok(ancestor == target_hash)
+		};
+
+		match is_valid_vote() {
+			Ok(true) => {},
+			Ok(false) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					hash = ?forced,
+					?target_hash,
+					?maybe_max_number,
+					"Ignoring the forced finality target, as it is not a valid vote",
+				);
+				return None
+			},
+			Err(e) => {
+				tracing::warn!(
+					target: LOG_TARGET,
+					hash = ?forced,
+					?target_hash,
+					err = ?e,
+					"Ignoring the forced finality target, as it could not be checked",
+				);
+				return None
+			},
+		}
+
+		tracing::warn!(
+			target: LOG_TARGET,
+			hash = ?forced,
+			"Using the forced finality target instead of the chain selection rules",
+		);
+		self.metrics.on_forced_finality_target();
+
+		Some(forced)
+	}
+
+	pub(crate) fn block_header(&self, hash: Hash) -> Result<PolkadotHeader, ConsensusError> {
+		match HeaderProvider::header(self.backend.header_provider(), hash) {
+			Ok(Some(header)) => Ok(header),
+			Ok(None) => Err(ConsensusError::ChainLookup(format!(
+				"Missing header with hash {:?}",
+				hash,
+			))),
+			Err(e) => Err(ConsensusError::ChainLookup(format!(
+				"Lookup failed for header with hash {:?}: {:?}",
+				hash,
+				e,
+			))),
+		}
+	}
+
+	pub(crate) fn block_number(&self, hash: Hash) -> Result<BlockNumber, ConsensusError> {
+		match HeaderProvider::number(self.backend.header_provider(), hash) {
+			Ok(Some(number)) => Ok(number),
+			Ok(None) => Err(ConsensusError::ChainLookup(format!(
+				"Missing number with hash {:?}",
+				hash,
+			))),
+			Err(e) => Err(ConsensusError::ChainLookup(format!(
+				"Lookup failed for number with hash {:?}: {:?}",
+				hash,
+				e,
+			))),
+		}
+	}
+
+	fn finalized_number(&self) -> Result<BlockNumber, ConsensusError> {
+		HeaderProvider::finalized_number(self.backend.header_provider()).map_err(|e| ConsensusError::ChainLookup(format!(
+			"Lookup failed for the number of the last finalized block: {:?}",
+			e,
+		)))
+	}
+
+	/// Ask the chain selection subsystem for the current leaves, bypassing the cache.
+	async fn fetch_leaves(&self) -> Result<Vec<Hash>, ConsensusError> {
+		let (tx, rx) = oneshot::channel();
+
+		self.send_msg(ChainSelectionMessage::Leaves(tx)).await;
+
+		rx.await.map_err(overseer_disconnected("Leaves"))
+	}
+
+	/// The cached leaves, regardless of their age, or an empty set if there are none.
+	///
+	/// The cache holds the leaves fetched by the last successful [`leaves`](SelectChain::leaves)
+	/// call, or by a [`best_chain`](SelectChain::best_chain) call which found a new best leaf
+	/// since, and is cleared by [`note_session`](Self::note_session) on a new session. Unlike
+	/// [`leaves`](SelectChain::leaves), this never consults the overseer.
+	pub fn known_leaves_set(&self) -> Vec<Hash> {
+		self.leaves_cache.lock().ok()
+			.and_then(|cache| cache.as_ref().map(|(leaves, _)| leaves.clone()))
+			.unwrap_or_default()
+	}
+
+	/// The cached leaves, if they are not older than the cache TTL.
+	fn cached_leaves(&self) -> Option<Vec<Hash>> {
+		let cache = self.leaves_cache.lock().ok()?;
+		match *cache {
+			Some((ref leaves, fetched_at)) if fetched_at.elapsed() < self.leaves_cache_ttl =>
+				Some(leaves.clone()),
+			_ => None,
+		}
+	}
+
+	/// Replace the cached leaves.
+	///
+	/// If `only_on_new_best` is set, the cache is only replaced if the best leaf differs
+	/// from the cached one.
+	fn cache_leaves(&self, leaves: &[Hash], only_on_new_best: bool) {
+		if let Ok(mut cache) = self.leaves_cache.lock() {
+			if only_on_new_best {
+				let cached_best = cache.as_ref().and_then(|(cached, _)| cached.first());
+				if cached_best == leaves.first() {
+					return
+				}
 			}
+
+			*cache = Some((leaves.to_vec(), Instant::now()));
+		}
+	}
+
+	/// Get the header of the best leaf containing the block with the given hash,
+	/// or `None` if there is no viable leaf containing the block.
+	///
+	/// `None` is also returned if chain selection does not respond within the
+	/// finality target timeout.
+	pub async fn best_chain_containing(
+		&self,
+		block_hash: Hash,
+	) -> Result<Option<PolkadotHeader>, ConsensusError> {
+		self.best_leaf_containing(block_hash)
+			.await?
+			.map(|best| self.block_header(best))
+			.transpose()
+	}
+
+	/// Whether the block with the given hash has been fully approved by the approval
+	/// voting subsystem.
+	///
+	/// Approval voting only tracks unfinalized blocks, so this returns `false` for
+	/// blocks at or below the last finalized block, apart from the genesis block,
+	/// which is always considered approved. It also returns `false` if approval voting
+	/// does not respond within the finality target timeout.
+	pub async fn is_approved(&self, hash: Hash) -> Result<bool, ConsensusError> {
+		let number = self.block_number(hash)?;
+		if number == 0 {
+			return Ok(true)
+		}
+
+		let (tx, rx) = oneshot::channel();
+		self.send_msg(ApprovalVotingMessage::ApprovedAncestor(hash, number - 1, tx)).await;
+
+		let approved_ancestor = self.await_response(rx, "ApprovedAncestor").await?.flatten();
+
+		Ok(approved_ancestor.map_or(false, |ancestor| ancestor.hash == hash))
+	}
+
+	/// Ask chain selection for the best leaf containing the block with the given hash.
+	///
+	/// Returns `None` if there is no viable leaf containing the block or if chain
+	/// selection does not respond within the finality target timeout.
+	async fn best_leaf_containing(&self, hash: Hash) -> Result<Option<Hash>, ConsensusError> {
+		let (tx, rx) = oneshot::channel();
+		self.send_msg(ChainSelectionMessage::BestLeafContaining(hash, tx)).await;
+
+		Ok(self.await_response(rx, "BestLeafContaining").await?.flatten())
+	}
+
+	/// Get up to `max_depth` ancestors of the block with the given hash, ordered from
+	/// oldest to newest.
+	///
+	/// The block itself is not included. Fewer ancestors are returned if the walk
+	/// reaches the genesis block.
+	pub fn ancestors_of(&self, hash: Hash, max_depth: u32) -> Result<Vec<Hash>, ConsensusError> {
+		let mut ancestors = Vec::new();
+		let mut header = self.block_header(hash)?;
+
+		while ancestors.len() < max_depth as usize && header.number > 0 {
+			let parent_hash = header.parent_hash;
+			header = self.block_header(parent_hash)?;
+			ancestors.push(parent_hash);
+		}
+
+		ancestors.reverse();
+		Ok(ancestors)
+	}
+
+	/// Get the header of the latest block at most `max_backtrack` blocks behind the best leaf
+	/// which is approved and not disputed, as a safe block to build on while many disputes
+	/// are active.
+	///
+	/// The dispute coordinator can only be told about the candidates of blocks known to approval
+	/// voting, which describes the approved blocks only, so unapproved blocks are never returned.
+	/// If the dispute coordinator does not even consider that block undisputed, or approval voting
+	/// or the dispute coordinator does not respond within the finality target timeout, the block
+	/// `max_backtrack` blocks behind the best leaf is returned.
+	pub async fn best_chain_without_disputes(
+		&self,
+		max_backtrack: u32,
+	) -> Result<PolkadotHeader, ConsensusError> {
+		let best = SelectChain::best_chain(self).await?;
+		let best_hash = best.hash();
+		let base_hash = match self.ancestors_of(best_hash, max_backtrack)?.first() {
+			Some(base_hash) => *base_hash,
+			None => return Ok(best),
+		};
+		let base_number = self.block_number(base_hash)?;
+
+		let (tx, rx) = oneshot::channel();
+		self.metrics.note_overseer_msg_bytes((best_hash, base_number).encoded_size());
+		self.send_msg(ApprovalVotingMessage::ApprovedAncestor(best_hash, base_number, tx)).await;
+		let (approved_number, descriptions) = match self.await_response(rx, "ApprovedAncestor").await? {
+			None | Some(None) => return self.block_header(base_hash),
+			Some(Some(HighestApprovedAncestorBlock { number, descriptions, .. })) => (number, descriptions),
+		};
+
+		let undisputed_chain = self.determine_undisputed_chain(
+			self.dispute_coordinators(),
+			base_number,
+			descriptions,
+		).await;
+		// Not even the base block is undisputed unless the dispute coordinator says otherwise.
+		let undisputed_hash = match undisputed_chain {
+			Ok(undisputed_chain) => undisputed_chain.map_or(base_hash, |(_, hash)| hash),
+			Err(e) if is_dispute_coordinator_timeout(&e) => base_hash,
+			Err(e) => return Err(e),
+		};
+
+		tracing::debug!(
+			target: LOG_TARGET,
+			best = ?best_hash,
+			?undisputed_hash,
+			approved_number,
+			"Determined the best chain without disputes",
+		);
+
+		self.block_header(undisputed_hash)
+	}
+
+	/// Get the latest ancestor of `target`, or `target` itself, which is approved and not
+	/// disputed, as a safe block to build on.
+	///
+	/// At most `max_finality_lag` blocks are considered: the block that far behind `target` is
+	/// assumed to be undisputed, and returned if the dispute coordinator does not even consider
+	/// it undisputed. Like [`best_chain_without_disputes`](Self::best_chain_without_disputes),
+	/// unapproved blocks are never returned. Returns `None` if approval voting does not respond
+	/// within the finality target timeout, and fails with [`Error::DisputeCoordinatorTimeout`]
+	/// if the dispute coordinator does not.
+	pub async fn disputes_chain_head(&self, target: Hash) -> Result<Option<Hash>, ConsensusError> {
+		let base_hash = match self.ancestors_of(target, self.max_finality_lag)?.first() {
+			Some(base_hash) => *base_hash,
+			None => return Ok(Some(target)),
+		};
+		let base_number = self.block_number(base_hash)?;
+
+		let (tx, rx) = oneshot::channel();
+		self.metrics.note_overseer_msg_bytes((target, base_number).encoded_size());
+		self.send_msg(ApprovalVotingMessage::ApprovedAncestor(target, base_number, tx)).await;
+		let descriptions = match self.await_response(rx, "ApprovedAncestor").await? {
+			None => return Ok(None),
+			Some(None) => return Ok(Some(base_hash)),
+			Some(Some(HighestApprovedAncestorBlock { descriptions, .. })) => descriptions,
+		};
+
+		let undisputed_chain = self.determine_undisputed_chain(
+			self.dispute_coordinators(),
+			base_number,
+			descriptions,
+		).await?;
+
+		Ok(Some(undisputed_chain.map_or(base_hash, |(_, hash)| hash)))
+	}
+
+	/// Get the number of blocks between the block with the given hash and its latest approved
+	/// ancestor, i.e. how far approval checking lags behind at that block.
+	///
+	/// Approval voting only tracks unfinalized blocks, so ancestors are looked up down to the
+	/// last finalized block, which counts as approved.
+	///
+	/// Returns `None` if approval voting does not respond within the finality target timeout.
+	pub async fn approval_lag_at(&self, hash: Hash) -> Result<Option<BlockNumber>, ConsensusError> {
+		let number = self.block_number(hash)?;
+		let finalized_number = self.finalized_number()?;
+
+		let (tx, rx) = oneshot::channel();
+		self.metrics.note_overseer_msg_bytes((hash, finalized_number).encoded_size());
+		self.send_msg(ApprovalVotingMessage::ApprovedAncestor(hash, finalized_number, tx)).await;
+		let approved_number = match self.await_response(rx, "ApprovedAncestor").await? {
+			None => return Ok(None),
+			// No approved ancestors beyond the last finalized block.
+			Some(None) => finalized_number,
+			Some(Some(HighestApprovedAncestorBlock { number, .. })) => number,
+		};
+
+		Ok(Some(number.saturating_sub(approved_number)))
+	}
+
+	/// Like `finality_target`, but without the maximum finality lag safety net, so any
+	/// finality lag caused by approval checking or disputes is kept.
+	///
+	/// This is meant for testing scenarios which deliberately cause a large finality lag.
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub async fn finality_target_unconstrained(
+		&self,
+		target_hash: Hash,
+		maybe_max_number: Option<BlockNumber>,
+	) -> Result<Option<Hash>, ConsensusError> {
+		self.finality_target_with_lag_limit(target_hash, maybe_max_number, None).await
+	}
+
+	/// The implementation of `finality_target`, applying the maximum finality lag safety net
+	/// only if `max_finality_lag` is given.
+	async fn finality_target_with_lag_limit(
+		&self,
+		target_hash: Hash,
+		maybe_max_number: Option<BlockNumber>,
+		max_finality_lag: Option<BlockNumber>,
+	) -> Result<Option<Hash>, ConsensusError> {
+		let _timer = self.metrics.time_finality_target();
+		let _ = self.reconnect_if_needed();
+
+		if let Some(forced) = self.forced_finality_target(target_hash, maybe_max_number) {
+			return Ok(Some(forced))
+		}
+
+		// `BestLeafContaining` only carries a hash, so its size is not worth observing.
+		let subchain_head = match self.best_leaf_containing(target_hash).await? {
+			// No viable leaves containing the block.
+			None => return Ok(Some(target_hash)),
+			Some(best) => best,
 		};
 
 		let target_number = self.block_number(target_hash)?;
@@ -411,19 +1470,19 @@ where
 		let (subchain_head, subchain_number, subchain_block_descriptions) = {
 
 			let (tx, rx) = oneshot::channel();
-			overseer.send_msg(
-				ApprovalVotingMessage::ApprovedAncestor(
-					subchain_head,
-					target_number,
-					tx,
-				),
-				std::any::type_name::<Self>(),
-			).await;
+			self.metrics.note_overseer_msg_bytes((subchain_head, target_number).encoded_size());
+			self.send_msg(ApprovalVotingMessage::ApprovedAncestor(
+				subchain_head,
+				target_number,
+				tx,
+			)).await;
 
-			match rx.await
-				.map_err(Error::OverseerDisconnected)
-				.map_err(|e| ConsensusError::Other(Box::new(e)))?
-			{
+			let approved_ancestor = match self.await_response(rx, "ApprovedAncestor").await? {
+				Some(approved_ancestor) => approved_ancestor,
+				None => return Ok(Some(target_hash)),
+			};
+
+			match approved_ancestor {
 				// No approved ancestors means target hash is maximal vote.
 				None => (target_hash, target_number, Vec::new()),
 				Some(HighestApprovedAncestorBlock {
@@ -447,29 +1506,41 @@ where
 		let lag = initial_leaf_number.saturating_sub(subchain_number);
 		self.metrics.note_approval_checking_finality_lag(lag);
 
-		// 3. Constrain according to disputes:
-		let (tx, rx) = oneshot::channel();
-		overseer.send_msg(DisputeCoordinatorMessage::DetermineUndisputedChain{
-				base_number: target_number,
-				block_descriptions: subchain_block_descriptions,
-				tx,
-			},
-			std::any::type_name::<Self>(),
-		).await;
-		let (subchain_number, subchain_head) = rx.await
-			.map_err(Error::OverseerDisconnected)
-			.map_err(|e| ConsensusError::Other(Box::new(e)))?
-			.unwrap_or_else(|| (subchain_number, subchain_head));
+		// 3. Constrain according to disputes. If no block beyond the target is approved,
+		// there is nothing for the dispute coordinator to constrain.
+		let (subchain_number, subchain_head) = if subchain_head == target_hash
+			&& subchain_block_descriptions.is_empty()
+		{
+			(subchain_number, subchain_head)
+		} else {
+			let undisputed_chain = self.determine_undisputed_chain(
+				self.dispute_coordinators(),
+				target_number,
+				subchain_block_descriptions,
+			).await;
+			match undisputed_chain {
+				Ok(undisputed_chain) => undisputed_chain.unwrap_or((target_number, target_hash)),
+				Err(e) if is_dispute_coordinator_timeout(&e) => return Ok(Some(target_hash)),
+				Err(e) => return Err(e),
+			}
+		};
 
 		// The the total lag accounting for disputes.
 		let lag_disputes = initial_leaf_number.saturating_sub(subchain_number);
 		self.metrics.note_disputes_finality_lag(lag_disputes);
+		self.notify_finality_lag(lag, lag_disputes);
+		self.report_finality_lag(lag, lag_disputes);
+		self.emit_slow_finality_warning(lag);
 
-		// 4. Apply the maximum safeguard to the finality lag.
-		if lag > MAX_FINALITY_LAG {
+		// 4. Apply the maximum safeguard to the finality lag, unless it is skipped.
+		let max_finality_lag = match max_finality_lag {
+			Some(max_finality_lag) => max_finality_lag,
+			None => return Ok(Some(subchain_head)),
+		};
+		if lag > max_finality_lag {
 			// We need to constrain our vote as a safety net to
 			// ensure the network continues to finalize.
-			let safe_target = initial_leaf_number - MAX_FINALITY_LAG;
+			let safe_target = initial_leaf_number - max_finality_lag;
 
 			if safe_target <= target_number {
 				// Minimal vote needs to be on the target number.
@@ -489,4 +1560,304 @@ where
 			Ok(Some(subchain_head))
 		}
 	}
+
+	/// Ask the dispute coordinators behind the given handles for the undisputed chain starting
+	/// at `base_number`, taking the most conservative, i.e. shortest, of their responses.
+	///
+	/// `None` means that even the base block should not be finalized, which is the most
+	/// conservative response, so it is returned if any of the dispute coordinators returns it.
+	/// The request fails with [`Error::DisputeCoordinatorTimeout`] if any of them did not respond
+	/// within the finality target timeout, and without any handles, as if the overseer was
+	/// disconnected.
+	async fn determine_undisputed_chain(
+		&self,
+		handles: Vec<OH>,
+		base_number: BlockNumber,
+		block_descriptions: Vec<BlockDescription>,
+	) -> Result<Option<(BlockNumber, Hash)>, ConsensusError> {
+		const REQUEST: &str = "DetermineUndisputedChain";
+
+		if handles.is_empty() {
+			return Err(overseer_disconnected(REQUEST)(oneshot::Canceled))
+		}
+
+		let msg_bytes = base_number.encoded_size() + encoded_size_of(&block_descriptions);
+		let mut responses = Vec::with_capacity(handles.len());
+		for mut handle in handles {
+			let (tx, rx) = oneshot::channel();
+			self.metrics.note_overseer_msg_bytes(msg_bytes);
+			handle.send_msg(
+				DisputeCoordinatorMessage::DetermineUndisputedChain {
+					base_number,
+					block_descriptions: block_descriptions.clone(),
+					tx,
+				},
+				std::any::type_name::<Self>(),
+			).await;
+			responses.push(self.await_response(rx, REQUEST));
+		}
+
+		let mut undisputed_chain: Option<Option<(BlockNumber, Hash)>> = None;
+		for response in future::join_all(responses).await {
+			let response = match response? {
+				Some(response) => response,
+				None => return Err(ConsensusError::Other(Box::new(
+					Error::DisputeCoordinatorTimeout(self.finality_target_timeout),
+				))),
+			};
+
+			undisputed_chain = Some(match (undisputed_chain, response) {
+				(None, response) => response,
+				(Some(Some(current)), Some(response)) => Some(if response.0 < current.0 { response } else { current }),
+				(Some(None), _) | (Some(Some(_)), None) => None,
+			});
+		}
+
+		// There is a response for every handle, and there is at least one handle.
+		Ok(undisputed_chain.flatten())
+	}
+
+	/// Await a subsystem response for `finality_target`.
+	///
+	/// Returns `Ok(None)` if no response arrived within the finality target timeout.
+	async fn await_response<T>(
+		&self,
+		rx: oneshot::Receiver<T>,
+		request: &'static str,
+	) -> Result<Option<T>, ConsensusError> {
+		let response = rx.timeout(self.finality_target_timeout);
+		futures::pin_mut!(response);
+
+		let response = match future::select(response, self.shutdown.clone()).await {
+			Either::Left((response, _)) => response,
+			Either::Right(_) => {
+				tracing::debug!(target: LOG_TARGET, request, "Canceled a subsystem request on shutdown");
+				return Err(ConsensusError::Other(Box::new(Error::Shutdown(request))))
+			}
+		};
+
+		match response {
+			Some(response) => response.map(Some).map_err(overseer_disconnected(request)),
+			None => {
+				// Unlike a canceled request, which means the overseer or the subsystem is gone,
+				// a timeout means the subsystem is merely slow.
+				tracing::warn!(
+					target: LOG_TARGET,
+					request,
+					timeout = ?self.finality_target_timeout,
+					"Timed out waiting for a subsystem response in `finality_target`",
+				);
+				self.metrics.on_finality_target_timeout(request);
+				Ok(None)
+			}
+		}
+	}
+}
+
+impl<B, OH, M> Clone for SelectRelayChain<B, OH, M>
+where
+	B: HeaderProviderProvider<PolkadotBlock> + Send + Sync,
+	OH: OverseerHandleT,
+	M: SelectChainMetricsT,
+{
+	fn clone(&self) -> Self {
+		SelectRelayChain {
+			backend: self.backend.clone(),
+			overseer: self.overseer.clone(),
+			dispute_coordinators: self.dispute_coordinators.clone(),
+			metrics: self.metrics.clone(),
+			finality_target_timeout: self.finality_target_timeout,
+			max_finality_lag: self.max_finality_lag,
+			slow_finality_warn_threshold: self.slow_finality_warn_threshold,
+			leaves_cache: self.leaves_cache.clone(),
+			leaves_cache_ttl: self.leaves_cache_ttl,
+			forced_finality_target: self.forced_finality_target.clone(),
+			finality_lag_subscriber: self.finality_lag_subscriber.clone(),
+			connected: self.connected.clone(),
+			session: self.session.clone(),
+			last_finality_lag: self.last_finality_lag.clone(),
+			shutdown: self.shutdown.clone(),
+			shutdown_tx: self.shutdown_tx.clone(),
+			telemetry: self.telemetry.clone(),
+		}
+	}
+}
+
+/// The errors of chain selection requests, wrapped in [`ConsensusError::Other`].
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	/// A request to a subsystem was canceled, e.g. because the message could not be sent
+	/// to the overseer or the overseer exited.
+	#[error("Overseer is disconnected, the {0} request was canceled")]
+	OverseerDisconnected(&'static str, #[source] oneshot::Canceled),
+	/// Chain selection returned empty leaves.
+	#[error("ChainSelection returned no leaves")]
+	EmptyLeaves,
+	/// Chain selection was shut down while waiting for the response to a request.
+	#[error("Chain selection was shut down, the {0} request was canceled")]
+	Shutdown(&'static str),
+	/// A dispute coordinator did not determine the undisputed chain within the finality
+	/// target timeout.
+	#[error("Dispute coordinator did not determine the undisputed chain within {0:?}")]
+	DisputeCoordinatorTimeout(Duration),
+}
+
+/// Whether the given error is an [`Error::DisputeCoordinatorTimeout`].
+fn is_dispute_coordinator_timeout(err: &ConsensusError) -> bool {
+	match err {
+		ConsensusError::Other(err) => matches!(err.downcast_ref::<Error>(), Some(Error::DisputeCoordinatorTimeout(_))),
+		_ => false,
+	}
+}
+
+/// The encoded size of the given block descriptions, which are not encodable themselves.
+fn encoded_size_of(block_descriptions: &[BlockDescription]) -> usize {
+	Compact(block_descriptions.len() as u32).encoded_size() + block_descriptions.iter()
+		.map(|description| (&description.block_hash, &description.session, &description.candidates).encoded_size())
+		.sum::<usize>()
+}
+
+/// Map the cancellation of the given request to a [`ConsensusError`].
+///
+/// A failure to send a request drops its response channel, so this covers send failures as well.
+fn overseer_disconnected(request: &'static str) -> impl FnOnce(oneshot::Canceled) -> ConsensusError {
+	move |e| ConsensusError::Other(Box::new(Error::OverseerDisconnected(request, e)))
+}
+
+/// Relay chain specific extensions of [`SelectChain`].
+pub trait SelectChainExt: SelectChain<PolkadotBlock> {
+	/// Get up to `max_depth` ancestors of the block with the given hash, ordered from
+	/// oldest to newest.
+	fn ancestors_of(&self, hash: Hash, max_depth: u32) -> Result<Vec<Hash>, ConsensusError>;
+}
+
+impl<B, OH, M> SelectChainExt for SelectRelayChain<B, OH, M>
+where
+	B: HeaderProviderProvider<PolkadotBlock>,
+	OH: OverseerHandleT,
+	M: SelectChainMetricsT,
+{
+	fn ancestors_of(&self, hash: Hash, max_depth: u32) -> Result<Vec<Hash>, ConsensusError> {
+		SelectRelayChain::ancestors_of(self, hash, max_depth)
+	}
+}
+
+impl<B> SelectChainExt for SelectRelayChainWithFallback<B>
+where
+	B: sc_client_api::Backend<PolkadotBlock> + 'static,
+{
+	fn ancestors_of(&self, hash: Hash, max_depth: u32) -> Result<Vec<Hash>, ConsensusError> {
+		self.selection.ancestors_of(hash, max_depth)
+	}
+}
+
+/// Decoupling trait for the overseer handle.
+///
+/// Required for testing purposes.
+#[async_trait::async_trait]
+pub trait OverseerHandleT: Clone + Send + Sync {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str);
+
+	/// Best-effort send of a message for non-async contexts.
+	///
+	/// Returns whether the message was sent. The default implementation polls
+	/// [`send_msg`](Self::send_msg) once and gives up if it would have to wait.
+	fn try_send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) -> bool {
+		self.send_msg(msg, origin).now_or_never().is_some()
+	}
+
+	/// Whether messages sent through the handle reach a running overseer.
+	fn is_connected(&self) -> bool {
+		true
+	}
+}
+
+#[async_trait::async_trait]
+impl OverseerHandleT for Handle {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) {
+		Handle::send_msg(self, msg, origin).await
+	}
+
+	fn try_send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) -> bool {
+		Handle::try_send_msg(self, msg, origin)
+	}
+
+	fn is_connected(&self) -> bool {
+		!self.is_disconnected() && !self.is_closed()
+	}
+}
+
+
+#[async_trait::async_trait]
+impl<B, OH, M> SelectChain<PolkadotBlock> for SelectRelayChain<B, OH, M>
+where
+	B: HeaderProviderProvider<PolkadotBlock>,
+	OH: OverseerHandleT,
+	M: SelectChainMetricsT,
+{
+	/// Get all leaves of the chain, i.e. block hashes that are suitable to
+	/// build upon and have no suitable children.
+	///
+	/// Leaves are cached for the leaves cache TTL, so rapid repeated calls
+	/// do not each query the chain selection subsystem.
+	async fn leaves(&self) -> Result<Vec<Hash>, ConsensusError> {
+		let _ = self.reconnect_if_needed();
+
+		if let Some(leaves) = self.cached_leaves() {
+			return Ok(leaves)
+		}
+
+		let leaves = self.fetch_leaves().await?;
+		self.cache_leaves(&leaves, false);
+
+		Ok(leaves)
+	}
+
+	/// Among all leaves, pick the one which is the best chain to build upon.
+	async fn best_chain(&self) -> Result<PolkadotHeader, ConsensusError> {
+		let _ = self.reconnect_if_needed();
+
+		// The leaves are always fetched, and the cache is refreshed if the best
+		// leaf changed.
+		let leaves = self.fetch_leaves().await?;
+		self.cache_leaves(&leaves, true);
+
+		// The Chain Selection subsystem is supposed to treat the finalized
+		// block as the best leaf in the case that there are no viable
+		// leaves, so this should not happen in practice.
+		let best_leaf = match leaves.first() {
+			Some(best_leaf) => *best_leaf,
+			None => {
+				tracing::error!(target: LOG_TARGET, "Chain selection returned no leaves");
+				self.metrics.on_empty_leaves();
+				return Err(ConsensusError::Other(Box::new(Error::EmptyLeaves)))
+			}
+		};
+
+
+		self.block_header(best_leaf)
+	}
+
+	/// Get the best descendant of `target_hash` that we should attempt to
+	/// finalize next, if any. It is valid to return the `target_hash` if
+	/// no better block exists.
+	///
+	/// This will search all leaves to find the best one containing the
+	/// given target hash, and then constrain to the given block number.
+	///
+	/// It will also constrain the chain to only chains which are fully
+	/// approved, and chains which contain no disputes.
+	///
+	/// If any of the subsystems does not respond within the finality target
+	/// timeout, the `target_hash` is returned.
+	///
+	/// If a finality target was forced, it is returned instead, as long as it is a valid vote
+	/// for `target_hash`. Otherwise, it is ignored.
+	async fn finality_target(
+		&self,
+		target_hash: Hash,
+		maybe_max_number: Option<BlockNumber>,
+	) -> Result<Option<Hash>, ConsensusError> {
+		self.finality_target_with_lag_limit(target_hash, maybe_max_number, Some(self.max_finality_lag)).await
+	}
 }
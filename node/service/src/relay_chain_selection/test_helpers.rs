@@ -0,0 +1,122 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for testing block production pipelines built on [`SelectRelayChain`](super::SelectRelayChain)
+//! without running an overseer.
+
+use std::sync::{Arc, Mutex};
+
+use futures::channel::oneshot;
+use polkadot_overseer::AllMessages;
+use polkadot_primitives::v1::{BlockNumber, Hash};
+use polkadot_subsystem::messages::{
+	ApprovalVotingMessage, ChainSelectionMessage, DisputeCoordinatorMessage, HighestApprovedAncestorBlock,
+};
+
+use super::OverseerHandleT;
+
+/// The responses a [`RecordingOverseerHandle`] gives to the requests of `SelectRelayChain`.
+#[derive(Debug, Clone, Default)]
+pub struct CannedResponses {
+	/// The response to `ChainSelectionMessage::Leaves`.
+	pub leaves: Vec<Hash>,
+	/// The response to `ChainSelectionMessage::BestLeafContaining`.
+	pub best_leaf_containing: Option<Hash>,
+	/// The response to `ApprovalVotingMessage::ApprovedAncestor`.
+	pub approved_ancestor: Option<HighestApprovedAncestorBlock>,
+	/// The response to `DisputeCoordinatorMessage::DetermineUndisputedChain`.
+	pub undisputed_chain: Option<(BlockNumber, Hash)>,
+}
+
+/// An overseer handle which records the messages sent through it rather than sending them,
+/// answering the requests of `SelectRelayChain` with [`CannedResponses`].
+///
+/// The response channels of answered requests are used up by answering them, so they are
+/// replaced by closed channels in the recorded messages.
+#[derive(Clone, Default)]
+pub struct RecordingOverseerHandle {
+	responses: Arc<Mutex<CannedResponses>>,
+	messages: Arc<Mutex<Vec<AllMessages>>>,
+}
+
+impl RecordingOverseerHandle {
+	/// Create a handle answering requests with the given responses.
+	pub fn new(responses: CannedResponses) -> Self {
+		RecordingOverseerHandle {
+			responses: Arc::new(Mutex::new(responses)),
+			messages: Default::default(),
+		}
+	}
+
+	/// Replace the responses to all further requests, including those of clones of this handle.
+	pub fn set_responses(&self, responses: CannedResponses) {
+		*self.responses.lock().expect("poisoned lock") = responses;
+	}
+
+	/// Take all messages recorded so far, in the order they were sent.
+	pub fn take_messages(&self) -> Vec<AllMessages> {
+		std::mem::take(&mut *self.messages.lock().expect("poisoned lock"))
+	}
+
+	/// Answer the message if it is a request of `SelectRelayChain`, returning the message to record.
+	fn respond(&self, msg: AllMessages) -> AllMessages {
+		fn closed<T>() -> oneshot::Sender<T> {
+			oneshot::channel().0
+		}
+
+		let responses = self.responses.lock().expect("poisoned lock").clone();
+		match msg {
+			AllMessages::ChainSelection(ChainSelectionMessage::Leaves(tx)) => {
+				let _ = tx.send(responses.leaves);
+				ChainSelectionMessage::Leaves(closed()).into()
+			}
+			AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(hash, tx)) => {
+				let _ = tx.send(responses.best_leaf_containing);
+				ChainSelectionMessage::BestLeafContaining(hash, closed()).into()
+			}
+			AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, number, tx)) => {
+				let _ = tx.send(responses.approved_ancestor);
+				ApprovalVotingMessage::ApprovedAncestor(hash, number, closed()).into()
+			}
+			AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+				base_number,
+				block_descriptions,
+				tx,
+			}) => {
+				let _ = tx.send(responses.undisputed_chain);
+				DisputeCoordinatorMessage::DetermineUndisputedChain {
+					base_number,
+					block_descriptions,
+					tx: closed(),
+				}.into()
+			}
+			msg => msg,
+		}
+	}
+}
+
+#[async_trait::async_trait]
+impl OverseerHandleT for RecordingOverseerHandle {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) {
+		let _ = self.try_send_msg(msg, origin);
+	}
+
+	fn try_send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, _origin: &'static str) -> bool {
+		let msg = self.respond(msg.into());
+		self.messages.lock().expect("poisoned lock").push(msg);
+		true
+	}
+}
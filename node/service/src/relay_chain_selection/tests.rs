@@ -0,0 +1,747 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use super::test_helpers::{CannedResponses, RecordingOverseerHandle};
+use crate::tests::{chain_0, linear_chain, TestChainStorage};
+
+use assert_matches::assert_matches;
+use polkadot_subsystem::messages::BlockDescription;
+use sp_runtime::traits::Header as _;
+
+fn select_relay_chain(
+	provider: TestChainStorage,
+	responses: CannedResponses,
+) -> (SelectRelayChain<TestChainStorage, RecordingOverseerHandle, NoopMetrics>, RecordingOverseerHandle) {
+	let overseer = RecordingOverseerHandle::new(responses);
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.backend(Arc::new(provider))
+		.overseer(overseer.clone())
+		.build();
+
+	(select_relay_chain, overseer)
+}
+
+fn block_description(block_hash: Hash) -> BlockDescription {
+	BlockDescription {
+		block_hash,
+		session: 1,
+		candidates: vec![],
+	}
+}
+
+#[test]
+fn builder_uses_defaults_unless_overridden() {
+	let provider = Arc::new(chain_0().chain);
+	let overseer = RecordingOverseerHandle::new(Default::default());
+
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.backend(provider.clone())
+		.overseer(overseer.clone())
+		.build();
+	assert_eq!(select_relay_chain.finality_target_timeout, DEFAULT_FINALITY_TARGET_TIMEOUT);
+	assert_eq!(select_relay_chain.max_finality_lag, DEFAULT_MAX_FINALITY_LAG);
+	assert_eq!(select_relay_chain.leaves_cache_ttl, DEFAULT_LEAVES_CACHE_TTL);
+
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.max_finality_lag(10)
+		.overseer(overseer)
+		.finality_target_timeout(Duration::from_secs(1))
+		.backend(provider)
+		.leaves_cache_ttl(Duration::from_secs(2))
+		.build();
+	assert_eq!(select_relay_chain.finality_target_timeout, Duration::from_secs(1));
+	assert_eq!(select_relay_chain.max_finality_lag, 10);
+	assert_eq!(select_relay_chain.leaves_cache_ttl, Duration::from_secs(2));
+}
+
+#[test]
+fn finality_target_returns_the_undisputed_approved_ancestor() {
+	let (provider, hashes) = linear_chain(4);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		best_leaf_containing: Some(hashes[4]),
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[3],
+			number: 3,
+			descriptions: vec![block_description(hashes[2]), block_description(hashes[3])],
+		}),
+		undisputed_chain: Some((3, hashes[3])),
+		..Default::default()
+	});
+
+	let target = futures::executor::block_on(select_relay_chain.finality_target(hashes[1], None)).unwrap();
+	assert_eq!(target, Some(hashes[3]));
+
+	let messages = overseer.take_messages();
+	assert_eq!(messages.len(), 3);
+	assert_matches!(
+		&messages[1],
+		AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, 1, _)) if *hash == hashes[4]
+	);
+	assert_matches!(
+		&messages[2],
+		AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+			base_number: 1,
+			block_descriptions,
+			..
+		}) if block_descriptions.len() == 2
+	);
+}
+
+#[test]
+fn finality_target_returns_the_target_without_a_best_leaf_containing_it() {
+	let (provider, hashes) = linear_chain(4);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		best_leaf_containing: None,
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[3],
+			number: 3,
+			descriptions: vec![block_description(hashes[2]), block_description(hashes[3])],
+		}),
+		undisputed_chain: Some((2, hashes[2])),
+		..Default::default()
+	});
+
+	let target = futures::executor::block_on(select_relay_chain.finality_target(hashes[1], None)).unwrap();
+	assert_eq!(target, Some(hashes[1]));
+
+	// Neither approval voting nor the dispute coordinator are asked.
+	let messages = overseer.take_messages();
+	assert_eq!(messages.len(), 1);
+	assert_matches!(
+		&messages[0],
+		AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(hash, _)) if *hash == hashes[1]
+	);
+}
+
+#[test]
+fn undisputed_chain_is_the_most_conservative_response() {
+	let (provider, hashes) = linear_chain(4);
+	let (select_relay_chain, _) = select_relay_chain(provider, Default::default());
+	let shard = |undisputed_chain| RecordingOverseerHandle::new(CannedResponses {
+		undisputed_chain,
+		..Default::default()
+	});
+	let descriptions = vec![block_description(hashes[2]), block_description(hashes[3])];
+	let determine_undisputed_chain = |handles| futures::executor::block_on(
+		select_relay_chain.determine_undisputed_chain(handles, 1, descriptions.clone()),
+	);
+
+	let shards = vec![shard(Some((3, hashes[3]))), shard(Some((2, hashes[2])))];
+	assert_eq!(determine_undisputed_chain(shards.clone()).unwrap(), Some((2, hashes[2])));
+	for shard in shards {
+		assert_matches!(
+			&shard.take_messages()[..],
+			[AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+				base_number: 1,
+				block_descriptions,
+				..
+			})] if block_descriptions.len() == 2
+		);
+	}
+
+	// Not even the base block being undisputed is the most conservative response.
+	assert_eq!(determine_undisputed_chain(vec![shard(Some((3, hashes[3]))), shard(None)]).unwrap(), None);
+	assert_eq!(determine_undisputed_chain(vec![shard(None), shard(Some((2, hashes[2])))]).unwrap(), None);
+	assert_matches!(determine_undisputed_chain(Vec::new()), Err(ConsensusError::Other(_)));
+}
+
+#[test]
+fn registered_dispute_coordinators_constrain_the_finality_target() {
+	let (provider, hashes) = linear_chain(4);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		best_leaf_containing: Some(hashes[4]),
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[3],
+			number: 3,
+			descriptions: vec![block_description(hashes[2]), block_description(hashes[3])],
+		}),
+		undisputed_chain: Some((3, hashes[3])),
+		..Default::default()
+	});
+	let finality_target = || futures::executor::block_on(
+		select_relay_chain.finality_target(hashes[1], None),
+	).unwrap();
+
+	let shard = RecordingOverseerHandle::new(CannedResponses {
+		undisputed_chain: Some((2, hashes[2])),
+		..Default::default()
+	});
+	select_relay_chain.clone().register_dispute_coordinator(shard.clone());
+	assert_eq!(finality_target(), Some(hashes[2]));
+	assert_matches!(
+		&shard.take_messages()[..],
+		[AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+			base_number: 1,
+			..
+		})]
+	);
+
+	// A shard which does not even consider the target undisputed keeps the vote on the target.
+	shard.set_responses(Default::default());
+	assert_eq!(finality_target(), Some(hashes[1]));
+	assert_eq!(overseer.take_messages().len(), 6);
+}
+
+#[test]
+fn best_chain_without_disputes_is_the_latest_approved_undisputed_block() {
+	let (provider, hashes) = linear_chain(6);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		leaves: vec![hashes[6]],
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[5],
+			number: 5,
+			descriptions: vec![block_description(hashes[4]), block_description(hashes[5])],
+		}),
+		undisputed_chain: Some((4, hashes[4])),
+		..Default::default()
+	});
+	let best_chain_without_disputes = |max_backtrack| futures::executor::block_on(
+		select_relay_chain.best_chain_without_disputes(max_backtrack),
+	).unwrap().hash();
+
+	assert_eq!(best_chain_without_disputes(3), hashes[4]);
+	let messages = overseer.take_messages();
+	assert_matches!(
+		&messages[1],
+		AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, 3, _)) if *hash == hashes[6]
+	);
+	assert_matches!(
+		&messages[2],
+		AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+			base_number: 3,
+			..
+		})
+	);
+
+	// Without backtracking, the best leaf is returned as is.
+	assert_eq!(best_chain_without_disputes(0), hashes[6]);
+	assert_eq!(overseer.take_messages().len(), 1);
+
+	// Without disputes, the approved ancestor is returned.
+	let approved_ancestor = Some(HighestApprovedAncestorBlock {
+		hash: hashes[5],
+		number: 5,
+		descriptions: vec![block_description(hashes[4]), block_description(hashes[5])],
+	});
+	overseer.set_responses(CannedResponses {
+		leaves: vec![hashes[6]],
+		approved_ancestor: approved_ancestor.clone(),
+		undisputed_chain: Some((5, hashes[5])),
+		..Default::default()
+	});
+	assert_eq!(best_chain_without_disputes(3), hashes[5]);
+
+	// If not even the base block is undisputed, the base block is returned.
+	overseer.set_responses(CannedResponses {
+		leaves: vec![hashes[6]],
+		approved_ancestor,
+		undisputed_chain: None,
+		..Default::default()
+	});
+	assert_eq!(best_chain_without_disputes(3), hashes[3]);
+
+	// Without approved blocks, the block `max_backtrack` blocks behind the best leaf is returned.
+	overseer.set_responses(CannedResponses {
+		leaves: vec![hashes[6]],
+		..Default::default()
+	});
+	assert_eq!(best_chain_without_disputes(3), hashes[3]);
+}
+
+#[test]
+fn disputes_chain_head_is_the_latest_approved_undisputed_ancestor() {
+	let (provider, hashes) = linear_chain(6);
+	let overseer = RecordingOverseerHandle::new(CannedResponses {
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[5],
+			number: 5,
+			descriptions: vec![block_description(hashes[3]), block_description(hashes[4]), block_description(hashes[5])],
+		}),
+		undisputed_chain: Some((4, hashes[4])),
+		..Default::default()
+	});
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.backend(Arc::new(provider))
+		.overseer(overseer.clone())
+		.max_finality_lag(4)
+		.build();
+
+	let head = futures::executor::block_on(select_relay_chain.disputes_chain_head(hashes[6])).unwrap();
+	assert_eq!(head, Some(hashes[4]));
+
+	let messages = overseer.take_messages();
+	assert_eq!(messages.len(), 2);
+	assert_matches!(
+		&messages[0],
+		AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, 2, _)) if *hash == hashes[6]
+	);
+	assert_matches!(
+		&messages[1],
+		AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain {
+			base_number: 2,
+			block_descriptions,
+			..
+		}) if block_descriptions.len() == 3
+	);
+
+	// If not even the base block is undisputed, the base block is returned.
+	overseer.set_responses(CannedResponses {
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[5],
+			number: 5,
+			descriptions: vec![block_description(hashes[3]), block_description(hashes[4]), block_description(hashes[5])],
+		}),
+		undisputed_chain: None,
+		..Default::default()
+	});
+	let head = futures::executor::block_on(select_relay_chain.disputes_chain_head(hashes[6])).unwrap();
+	assert_eq!(head, Some(hashes[2]));
+	assert_eq!(overseer.take_messages().len(), 2);
+
+	// Blocks are not walked back beyond the genesis block.
+	let head = futures::executor::block_on(select_relay_chain.disputes_chain_head(hashes[0])).unwrap();
+	assert_eq!(head, Some(hashes[0]));
+	assert!(overseer.take_messages().is_empty());
+}
+
+#[test]
+fn approval_lag_at_is_measured_from_the_latest_approved_ancestor() {
+	let (mut provider, hashes) = linear_chain(6);
+	provider.finalize(2);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[4],
+			number: 4,
+			descriptions: (3..=4).map(|i| block_description(hashes[i])).collect(),
+		}),
+		..Default::default()
+	});
+	let approval_lag_at = |hash| futures::executor::block_on(
+		select_relay_chain.approval_lag_at(hash),
+	).unwrap();
+
+	assert_eq!(approval_lag_at(hashes[6]), Some(2));
+	assert_matches!(
+		&overseer.take_messages()[..],
+		[AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, 2, _))] if *hash == hashes[6]
+	);
+
+	// Without any approved ancestors, the lag reaches back to the last finalized block.
+	overseer.set_responses(Default::default());
+	assert_eq!(approval_lag_at(hashes[6]), Some(4));
+}
+
+#[test]
+fn max_finality_lag_safety_net_fires_beyond_the_limit() {
+	const TARGET: usize = 5;
+
+	for &lag in &[DEFAULT_MAX_FINALITY_LAG - 1, DEFAULT_MAX_FINALITY_LAG, DEFAULT_MAX_FINALITY_LAG + 1] {
+		let initial_leaf_number = TARGET as BlockNumber + lag;
+		let (provider, hashes) = linear_chain(initial_leaf_number);
+
+		// nothing beyond the target is approved, so the approval checking lag is the distance
+		// between the target and the leaf.
+		let (select_relay_chain, _) = select_relay_chain(provider, CannedResponses {
+			best_leaf_containing: Some(hashes[initial_leaf_number as usize]),
+			approved_ancestor: None,
+			..Default::default()
+		});
+
+		let target = futures::executor::block_on(
+			select_relay_chain.finality_target(hashes[TARGET], None),
+		).unwrap();
+
+		if lag > DEFAULT_MAX_FINALITY_LAG {
+			let forced_number = initial_leaf_number - DEFAULT_MAX_FINALITY_LAG;
+			assert_eq!(target, Some(hashes[forced_number as usize]), "lag {}", lag);
+		} else {
+			assert_eq!(target, Some(hashes[TARGET]), "lag {}", lag);
+		}
+	}
+}
+
+#[test]
+fn slow_finality_warning_fires_beyond_the_threshold() {
+	use metrics::Metrics as _;
+
+	const TARGET: usize = 5;
+	const THRESHOLD: BlockNumber = 10;
+
+	let registry = prometheus::Registry::new();
+	let metrics = Metrics::try_register(&registry).unwrap();
+	let warnings = || registry.gather().into_iter()
+		.find(|family| family.get_name() == "parachain_slow_finality_warning_total")
+		.map(|family| family.get_metric()[0].get_counter().get_value() as u64);
+
+	for &(lag, expected_warnings) in &[(THRESHOLD - 1, 0), (THRESHOLD, 0), (THRESHOLD + 1, 1)] {
+		let initial_leaf_number = TARGET as BlockNumber + lag;
+		let (provider, hashes) = linear_chain(initial_leaf_number);
+		let select_relay_chain = SelectRelayChainBuilder::default()
+			.backend(Arc::new(provider))
+			.overseer(RecordingOverseerHandle::new(CannedResponses {
+				best_leaf_containing: Some(hashes[initial_leaf_number as usize]),
+				approved_ancestor: None,
+				..Default::default()
+			}))
+			.metrics(metrics.clone())
+			.slow_finality_warn_threshold(THRESHOLD)
+			.build();
+
+		// the warning doesn't constrain the finality target.
+		let target = futures::executor::block_on(
+			select_relay_chain.finality_target(hashes[TARGET], None),
+		).unwrap();
+		assert_eq!(target, Some(hashes[TARGET]), "lag {}", lag);
+		assert_eq!(warnings(), Some(expected_warnings), "lag {}", lag);
+	}
+}
+
+#[test]
+fn finality_target_duration_is_observed_on_all_exit_paths() {
+	use metrics::Metrics as _;
+
+	let registry = prometheus::Registry::new();
+	let (provider, hashes) = linear_chain(2);
+	let overseer = RecordingOverseerHandle::new(Default::default());
+	let select_relay_chain = SelectRelayChain::new(
+		Arc::new(provider),
+		overseer.clone(),
+		Metrics::try_register(&registry).unwrap(),
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	futures::executor::block_on(async {
+		// No leaf contains the target.
+		assert_eq!(select_relay_chain.finality_target(hashes[1], None).await.unwrap(), Some(hashes[1]));
+
+		// The target is unknown.
+		overseer.set_responses(CannedResponses {
+			best_leaf_containing: Some(hashes[2]),
+			..Default::default()
+		});
+		assert!(select_relay_chain.finality_target(Hash::repeat_byte(0xCC), None).await.is_err());
+	});
+
+	let sample_count = registry.gather().into_iter()
+		.find(|family| family.get_name() == "parachain_chain_selection_finality_target_duration_seconds")
+		.map(|family| family.get_metric()[0].get_histogram().get_sample_count());
+	assert_eq!(sample_count, Some(2));
+}
+
+#[test]
+fn finality_target_request_sizes_are_observed() {
+	use metrics::Metrics as _;
+
+	let registry = prometheus::Registry::new();
+	let (provider, hashes) = linear_chain(4);
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.backend(Arc::new(provider))
+		.overseer(RecordingOverseerHandle::new(CannedResponses {
+			best_leaf_containing: Some(hashes[4]),
+			approved_ancestor: Some(HighestApprovedAncestorBlock {
+				hash: hashes[3],
+				number: 3,
+				descriptions: vec![block_description(hashes[2]), block_description(hashes[3])],
+			}),
+			undisputed_chain: Some((3, hashes[3])),
+			..Default::default()
+		}))
+		.metrics(Metrics::try_register(&registry).unwrap())
+		.build();
+
+	futures::executor::block_on(select_relay_chain.finality_target(hashes[1], None)).unwrap();
+
+	let histogram = registry.gather().into_iter()
+		.find(|family| family.get_name() == "parachain_chain_selection_overseer_msg_bytes")
+		.map(|family| family.get_metric()[0].get_histogram().clone())
+		.unwrap();
+
+	// `BestLeafContaining` is not observed, `ApprovedAncestor` carries a hash and a block number
+	// and `DetermineUndisputedChain` a block number and two descriptions without candidates.
+	assert_eq!(histogram.get_sample_count(), 2);
+	assert_eq!(histogram.get_sample_sum() as usize, (32 + 4) + (4 + 1 + 2 * (32 + 4 + 1)));
+}
+
+#[test]
+fn known_leaves_set_returns_the_last_fetched_leaves() {
+	let (provider, hashes) = linear_chain(2);
+	let (select_relay_chain, overseer) = select_relay_chain(provider, CannedResponses {
+		leaves: vec![hashes[2], hashes[1]],
+		..Default::default()
+	});
+
+	assert!(select_relay_chain.known_leaves_set().is_empty());
+
+	futures::executor::block_on(select_relay_chain.leaves()).unwrap();
+	assert_eq!(select_relay_chain.known_leaves_set(), vec![hashes[2], hashes[1]]);
+
+	// Known leaves are returned without consulting the overseer.
+	let _ = overseer.take_messages();
+	overseer.set_responses(Default::default());
+	assert_eq!(select_relay_chain.known_leaves_set(), vec![hashes[2], hashes[1]]);
+	assert!(overseer.take_messages().is_empty());
+}
+
+#[test]
+fn session_changes_clear_the_caches() {
+	let (provider, hashes) = linear_chain(2);
+	let overseer = RecordingOverseerHandle::new(CannedResponses {
+		leaves: vec![hashes[2], hashes[1]],
+		..Default::default()
+	});
+	let select_relay_chain = SelectRelayChainBuilder::default()
+		.backend(Arc::new(provider))
+		.overseer(overseer.clone())
+		.leaves_cache_ttl(Duration::from_secs(600))
+		.build();
+
+	let fetch_leaves = || {
+		futures::executor::block_on(select_relay_chain.leaves()).unwrap();
+		overseer.take_messages().len()
+	};
+
+	select_relay_chain.note_session(1);
+	assert_eq!(fetch_leaves(), 1);
+
+	// The same session doesn't clear the cache.
+	select_relay_chain.note_session(1);
+	assert_eq!(fetch_leaves(), 0);
+	assert_eq!(select_relay_chain.known_leaves_set(), vec![hashes[2], hashes[1]]);
+
+	select_relay_chain.clone().note_session(2);
+	assert!(select_relay_chain.known_leaves_set().is_empty());
+	assert_eq!(fetch_leaves(), 1);
+}
+
+/// An overseer handle which can be told whether it reaches a running overseer.
+#[derive(Clone)]
+struct ToggleOverseerHandle {
+	inner: RecordingOverseerHandle,
+	connected: Arc<AtomicBool>,
+}
+
+#[async_trait::async_trait]
+impl OverseerHandleT for ToggleOverseerHandle {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) {
+		self.inner.send_msg(msg, origin).await
+	}
+
+	fn is_connected(&self) -> bool {
+		self.connected.load(Ordering::Relaxed)
+	}
+}
+
+#[test]
+fn connection_state_is_rechecked_by_requests() {
+	let (provider, hashes) = linear_chain(1);
+	let connected = Arc::new(AtomicBool::new(true));
+	let select_relay_chain = SelectRelayChain::new(
+		Arc::new(provider),
+		ToggleOverseerHandle {
+			inner: RecordingOverseerHandle::new(CannedResponses {
+				leaves: vec![hashes[1]],
+				..Default::default()
+			}),
+			connected: connected.clone(),
+		},
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	// Not checked yet.
+	assert!(!select_relay_chain.is_connected());
+
+	futures::executor::block_on(select_relay_chain.best_chain()).unwrap();
+	assert!(select_relay_chain.is_connected());
+
+	connected.store(false, Ordering::Relaxed);
+	let _ = futures::executor::block_on(select_relay_chain.leaves());
+	assert!(!select_relay_chain.is_connected());
+
+	connected.store(true, Ordering::Relaxed);
+	let _ = futures::executor::block_on(select_relay_chain.finality_target(hashes[0], None));
+	assert!(select_relay_chain.is_connected());
+}
+
+#[test]
+fn disconnect_and_reconnect_are_shared_among_clones() {
+	let (provider, hashes) = linear_chain(1);
+	let responses = CannedResponses {
+		leaves: vec![hashes[1]],
+		..Default::default()
+	};
+	let (select_relay_chain, overseer) = select_relay_chain(provider, responses.clone());
+	let clone = select_relay_chain.clone();
+
+	for _ in 0..2 {
+		assert_eq!(futures::executor::block_on(clone.best_chain()).unwrap().hash(), hashes[1]);
+		assert!(select_relay_chain.is_connected());
+		assert_eq!(overseer.take_messages().len(), 1);
+
+		// Requests fail without reaching the overseer.
+		select_relay_chain.disconnect();
+		assert_matches!(
+			futures::executor::block_on(clone.best_chain()),
+			Err(ConsensusError::Other(_))
+		);
+		assert!(!select_relay_chain.is_connected());
+		assert!(overseer.take_messages().is_empty());
+
+		// Disconnecting twice is harmless.
+		clone.disconnect();
+		assert!(futures::executor::block_on(select_relay_chain.best_chain()).is_err());
+
+		select_relay_chain.reconnect(overseer.clone());
+	}
+
+	// The new handle replaces the old one.
+	let new_overseer = RecordingOverseerHandle::new(responses);
+	clone.reconnect(new_overseer.clone());
+	futures::executor::block_on(select_relay_chain.best_chain()).unwrap();
+	assert!(overseer.take_messages().is_empty());
+	assert_eq!(new_overseer.take_messages().len(), 1);
+}
+
+#[test]
+fn last_finality_lag_is_noted() {
+	let (provider, hashes) = linear_chain(4);
+	let (select_relay_chain, _) = select_relay_chain(provider, CannedResponses {
+		best_leaf_containing: Some(hashes[4]),
+		approved_ancestor: Some(HighestApprovedAncestorBlock {
+			hash: hashes[3],
+			number: 3,
+			descriptions: vec![block_description(hashes[2]), block_description(hashes[3])],
+		}),
+		undisputed_chain: Some((2, hashes[2])),
+		..Default::default()
+	});
+
+	assert_eq!(select_relay_chain.last_finality_lag(), None);
+
+	futures::executor::block_on(select_relay_chain.finality_target(hashes[1], None)).unwrap();
+	assert_eq!(select_relay_chain.last_finality_lag(), Some(2));
+}
+
+/// An overseer handle which keeps all messages without ever answering them.
+#[derive(Clone, Default)]
+struct UnresponsiveOverseerHandle(Arc<Mutex<Vec<AllMessages>>>);
+
+#[async_trait::async_trait]
+impl OverseerHandleT for UnresponsiveOverseerHandle {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, _origin: &'static str) {
+		self.0.lock().unwrap().push(msg.into());
+	}
+}
+
+#[test]
+fn shutdown_cancels_pending_requests() {
+	let (provider, hashes) = linear_chain(1);
+	let select_relay_chain = SelectRelayChain::new(
+		Arc::new(provider),
+		UnresponsiveOverseerHandle::default(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+	let clone = select_relay_chain.clone();
+
+	let assert_shut_down = |result: Result<Option<Hash>, ConsensusError>| assert_matches!(
+		result,
+		Err(ConsensusError::Other(e)) if e.to_string().contains("shut down")
+	);
+
+	futures::executor::block_on(async {
+		let (result, ()) = future::join(
+			select_relay_chain.finality_target(hashes[0], None),
+			async { clone.shutdown() },
+		).await;
+		assert_shut_down(result);
+
+		// Requests after the shutdown are canceled right away.
+		assert_shut_down(select_relay_chain.finality_target(hashes[0], None).await);
+	});
+}
+
+#[test]
+fn timeouts_are_counted_per_request() {
+	use metrics::Metrics as _;
+
+	let registry = prometheus::Registry::new();
+	let (provider, hashes) = linear_chain(2);
+	let overseer = UnresponsiveOverseerHandle::default();
+	let select_relay_chain = SelectRelayChain::new(
+		Arc::new(provider),
+		overseer.clone(),
+		Metrics::try_register(&registry).unwrap(),
+		Duration::from_millis(10),
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	// The target is returned when chain selection does not respond in time.
+	let target = futures::executor::block_on(select_relay_chain.finality_target(hashes[1], None)).unwrap();
+	assert_eq!(target, Some(hashes[1]));
+
+	let timeouts = registry.gather().into_iter()
+		.find(|family| family.get_name() == "parachain_finality_target_timeout_total")
+		.map(|family| family.get_metric().iter()
+			.map(|metric| (metric.get_label()[0].get_value().to_owned(), metric.get_counter().get_value()))
+			.collect::<Vec<_>>()
+		);
+	assert_eq!(timeouts, Some(vec![("BestLeafContaining".to_owned(), 1.0)]));
+}
+
+#[test]
+fn dispute_coordinator_timeouts_are_distinguished() {
+	let (provider, hashes) = linear_chain(4);
+	let select_relay_chain = SelectRelayChain::new(
+		Arc::new(provider),
+		UnresponsiveOverseerHandle::default(),
+		NoopMetrics,
+		Duration::from_millis(10),
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	let result = futures::executor::block_on(select_relay_chain.determine_undisputed_chain(
+		vec![UnresponsiveOverseerHandle::default()],
+		1,
+		vec![block_description(hashes[2])],
+	));
+	assert_matches!(
+		result,
+		Err(ConsensusError::Other(e)) if matches!(
+			e.downcast_ref::<Error>(),
+			Some(Error::DisputeCoordinatorTimeout(timeout)) if *timeout == Duration::from_millis(10)
+		)
+	);
+}
+
+#[test]
+fn chain_selection_config_falls_back_to_defaults() {
+	let config: ChainSelectionConfig = serde_json::from_str(r#"{ "maxFinalityLag": 10 }"#).unwrap();
+
+	assert_eq!(config, ChainSelectionConfig { max_finality_lag: 10, ..Default::default() });
+	assert_eq!(config.finality_target_timeout(), DEFAULT_FINALITY_TARGET_TIMEOUT);
+	assert_eq!(config.leaves_cache_ttl(), DEFAULT_LEAVES_CACHE_TTL);
+	assert!(serde_json::from_str::<ChainSelectionConfig>(r#"{ "maxLag": 10 }"#).is_err());
+}
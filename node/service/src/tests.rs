@@ -36,7 +36,7 @@ use assert_matches::assert_matches;
 use std::sync::Arc;
 use std::time::Duration;
 
-use consensus_common::SelectChain;
+use consensus_common::{Error as ConsensusError, SelectChain};
 use futures::channel::oneshot;
 use futures::prelude::*;
 use polkadot_primitives::v1::{Block, BlockNumber, Hash, Header};
@@ -64,10 +64,22 @@ struct TestHarness {
 	finality_target_rx: Receiver<Option<Hash>>,
 }
 
-#[derive(Default)]
-struct HarnessConfig;
+struct HarnessConfig {
+	/// The maximum finality lag, or `None` to skip the safety net.
+	max_finality_lag: Option<BlockNumber>,
+}
+
+impl Default for HarnessConfig {
+	fn default() -> Self {
+		Self { max_finality_lag: Some(DEFAULT_MAX_FINALITY_LAG) }
+	}
+}
 
-fn test_harness<T: Future<Output = VirtualOverseer>>(case_vars: CaseVars, test: impl FnOnce(TestHarness) -> T) {
+fn test_harness<T: Future<Output = VirtualOverseer>>(
+	config: HarnessConfig,
+	case_vars: CaseVars,
+	test: impl FnOnce(TestHarness) -> T,
+) {
 	let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Trace).try_init();
 
 	let pool = sp_core::testing::TaskExecutor::new();
@@ -75,15 +87,21 @@ fn test_harness<T: Future<Output = VirtualOverseer>>(case_vars: CaseVars, test:
 
 	let (finality_target_tx, finality_target_rx) = oneshot::channel::<Option<Hash>>();
 
-	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender>::new(
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
 		Arc::new(case_vars.chain.clone()),
 		context.sender().clone(),
-		Default::default(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		config.max_finality_lag.unwrap_or(DEFAULT_MAX_FINALITY_LAG),
+		DEFAULT_LEAVES_CACHE_TTL,
 	);
 
 	let target_hash = case_vars.target_block.clone();
 	let selection_process = async move {
-		let best = select_relay_chain.finality_target(target_hash, None).await.unwrap();
+		let best = match config.max_finality_lag {
+			Some(_) => select_relay_chain.finality_target(target_hash, None).await,
+			None => select_relay_chain.finality_target_unconstrained(target_hash, None).await,
+		}.unwrap();
 		finality_target_tx.send(best).unwrap();
 		()
 	};
@@ -140,15 +158,21 @@ const B3: Hash = Hash::repeat_byte(0xB3);
 /// to extract information for finalization target
 /// extraction.
 #[derive(Debug, Default, Clone)]
-struct TestChainStorage {
+pub(crate) struct TestChainStorage {
 	blocks_by_hash: HashMap<Hash, Header>,
 	blocks_at_height: BTreeMap<u32, Vec<Hash>>,
 	disputed_blocks: HashSet<Hash>,
 	approved_blocks: HashSet<Hash>,
 	heads: HashSet<Hash>,
+	finalized_number: BlockNumber,
 }
 
 impl TestChainStorage {
+	/// Note the block with the given number as the last finalized block.
+	pub(crate) fn finalize(&mut self, number: BlockNumber) {
+		self.finalized_number = number;
+	}
+
 	/// Fill the [`HighestApprovedAncestor`] structure with mostly
 	/// correct data.
 	pub fn highest_approved_ancestors(
@@ -231,6 +255,9 @@ impl HeaderProvider<Block> for TestChainStorage {
 	fn number(&self, hash: Hash) -> sp_blockchain::Result<Option<BlockNumber>> {
 		self.header(hash).map(|opt| opt.map(|h| h.number))
 	}
+	fn finalized_number(&self) -> sp_blockchain::Result<BlockNumber> {
+		Ok(self.finalized_number)
+	}
 }
 
 impl HeaderProviderProvider<Block> for TestChainStorage {
@@ -263,6 +290,11 @@ impl ChainBuilder {
 
 	fn add_block_inner<'a>(&'a mut self, hash: Hash, parent_hash: Hash, number: u32) -> &'a mut Self {
 		let header = ChainBuilder::make_header(parent_hash, number);
+		self.insert_header(hash, header)
+	}
+
+	fn insert_header<'a>(&'a mut self, hash: Hash, header: Header) -> &'a mut Self {
+		let number = header.number;
 		assert!(self.0.blocks_by_hash.insert(hash, header).is_none(), "block with hash {:?} already exists", hash,);
 		self.0.blocks_at_height.entry(number).or_insert_with(Vec::new).push(hash);
 		self
@@ -298,6 +330,17 @@ impl ChainBuilder {
 		hash
 	}
 
+	/// Add a block which is stored under the hash of its header, for the code paths
+	/// which hash the headers they look up.
+	pub fn fast_forward_hashed(&mut self, parent: Hash, block_number: BlockNumber) -> Hash {
+		assert!(block_number != 0, "cannot add duplicate genesis block");
+		assert!(self.0.blocks_by_hash.len() < u8::MAX.into());
+		let header = ChainBuilder::make_header(parent, block_number);
+		let hash = header.hash();
+		let _ = self.insert_header(hash, header);
+		hash
+	}
+
 	pub fn set_heads(&mut self, heads: impl IntoIterator<Item = Hash>) {
 		self.0.heads = heads.into_iter().collect();
 	}
@@ -367,6 +410,14 @@ async fn test_skeleton(
 		}
 	);
 
+	// The dispute coordinator is only asked if a block beyond the target is approved.
+	let beyond_target_approved = highest_approved_ancestor_block.map_or(false, |approved| {
+		approved.hash != target_block_hash || !approved.descriptions.is_empty()
+	});
+	if !beyond_target_approved {
+		return;
+	}
+
 	tracing::trace!("determine undisputed chain response: {:?}", undisputed_chain);
 	assert_matches!(
 		overseer_recv(
@@ -386,7 +437,15 @@ async fn test_skeleton(
 /// Straight forward test case, where the test is not
 /// for integrity, but for different block relation structures.
 fn run_specialized_test_w_harness<F: FnOnce() -> CaseVars>(case_var_provider: F) {
-	test_harness(case_var_provider(), |test_harness| async move {
+	run_specialized_test_w_harness_and_config(HarnessConfig::default(), case_var_provider)
+}
+
+/// Like [`run_specialized_test_w_harness`], but with a custom harness configuration.
+fn run_specialized_test_w_harness_and_config<F: FnOnce() -> CaseVars>(
+	config: HarnessConfig,
+	case_var_provider: F,
+) {
+	test_harness(config, case_var_provider(), |test_harness| async move {
 		let TestHarness {
 			mut virtual_overseer,
 			finality_target_rx,
@@ -450,9 +509,9 @@ fn run_specialized_test_w_harness<F: FnOnce() -> CaseVars>(case_var_provider: F)
 
 /// All variables relevant for a test case.
 #[derive(Clone, Debug)]
-struct CaseVars {
+pub(crate) struct CaseVars {
 	/// Chain test _case_ definition.
-	chain: TestChainStorage,
+	pub(crate) chain: TestChainStorage,
 
 	/// The target block to be finalized.
 	target_block: Hash,
@@ -479,7 +538,7 @@ struct CaseVars {
 ///               \
 ///                `- 0xB2
 /// ```
-fn chain_0() -> CaseVars {
+pub(crate) fn chain_0() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -510,7 +569,7 @@ fn chain_0() -> CaseVars {
 ///               \
 ///                `- 0xB2 --- 0xB3(!available)
 /// ```
-fn chain_1() -> CaseVars {
+pub(crate) fn chain_1() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -538,7 +597,7 @@ fn chain_1() -> CaseVars {
 ///               \
 ///                `- 0xB2 --- 0xB3
 /// ```
-fn chain_2() -> CaseVars {
+pub(crate) fn chain_2() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -566,7 +625,7 @@ fn chain_2() -> CaseVars {
 ///               \
 ///                `- 0xB2 --- 0xB3
 /// ```
-fn chain_3() -> CaseVars {
+pub(crate) fn chain_3() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -596,7 +655,7 @@ fn chain_3() -> CaseVars {
 ///
 ///      ? --- NEX(does_not_exist)
 /// ```
-fn chain_4() -> CaseVars {
+pub(crate) fn chain_4() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -623,7 +682,7 @@ fn chain_4() -> CaseVars {
 /// ```raw
 /// genesis -- 0xA1 --- 0xA2
 /// ```
-fn chain_5() -> CaseVars {
+pub(crate) fn chain_5() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -645,7 +704,7 @@ fn chain_5() -> CaseVars {
 /// ```raw
 /// genesis -- 0xB2 -- 0xD2 -- .. -- 0xD8 -- 0xC8(unapproved) -- .. -- 0xCF(unapproved)
 /// ```
-fn chain_6() -> CaseVars {
+pub(crate) fn chain_6() -> CaseVars {
 	let head: Hash = ChainBuilder::GENESIS_HASH;
 	let mut builder = ChainBuilder::new();
 
@@ -679,6 +738,25 @@ fn chain_6() -> CaseVars {
 	}
 }
 
+/// ```raw
+/// genesis -- 1 --- 2 --- .. --- len
+/// ```
+///
+/// Returns the chain along with the hashes of all of its blocks, ordered by number. Unlike
+/// the other chains, blocks are stored under the hashes of their headers.
+pub(crate) fn linear_chain(len: BlockNumber) -> (TestChainStorage, Vec<Hash>) {
+	let mut builder = ChainBuilder::new();
+	let mut hashes = vec![ChainBuilder::GENESIS_HASH];
+	for block_number in 1..=len {
+		let parent = hashes[hashes.len() - 1];
+		hashes.push(builder.fast_forward_hashed(parent, block_number));
+	}
+
+	builder.set_heads(vec![hashes[hashes.len() - 1]]);
+
+	(builder.init(), hashes)
+}
+
 #[test]
 fn chain_sel_0() {
 	run_specialized_test_w_harness(chain_0);
@@ -713,3 +791,640 @@ fn chain_sel_5_best_is_target_hash() {
 fn chain_sel_6_approval_lag() {
 	run_specialized_test_w_harness(chain_6);
 }
+
+#[test]
+fn chain_sel_6_approval_lag_equal_to_max_finality_lag() {
+	// The approval checking lag of `chain_6` is 14 blocks, which does not exceed the limit.
+	run_specialized_test_w_harness_and_config(HarnessConfig { max_finality_lag: Some(14) }, chain_6);
+}
+
+#[test]
+fn chain_sel_6_approval_lag_exceeds_max_finality_lag() {
+	// The leaf is at block 15, so the safety net forces a vote on block 12.
+	run_specialized_test_w_harness_and_config(HarnessConfig { max_finality_lag: Some(3) }, || {
+		let mut case_vars = chain_6();
+		case_vars.expected_finality_target_result = Some(Hash::repeat_byte(0xAC));
+		case_vars
+	});
+}
+
+#[test]
+fn chain_sel_6_approval_lag_unconstrained() {
+	// Without the safety net, the approved ancestor is voted on despite the lag.
+	run_specialized_test_w_harness_and_config(HarnessConfig { max_finality_lag: None }, chain_6);
+}
+
+#[test]
+fn finality_target_falls_back_to_target_on_timeout() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let case_vars = chain_0();
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(case_vars.chain.clone()),
+		context.sender().clone(),
+		NoopMetrics,
+		Duration::from_millis(100),
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	let target_hash = case_vars.target_block;
+	futures::executor::block_on(async move {
+		let selection_process = select_relay_chain.finality_target(target_hash, None);
+		let overseer_process = async move {
+			// Hold on to the response sender without ever answering.
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(_, tx)) => tx
+			)
+		};
+
+		let (best, _tx) = future::join(selection_process, overseer_process).await;
+		assert_eq!(best.unwrap(), Some(target_hash));
+	});
+}
+
+#[test]
+fn leaves_are_cached_until_the_best_leaf_changes() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let chain = chain_0().chain;
+	let a5 = Hash::repeat_byte(0xA5);
+	let b4 = Hash::repeat_byte(0xB4);
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		Duration::from_secs(60),
+	);
+
+	futures::executor::block_on(async move {
+		let selection_process = async move {
+			assert_eq!(select_relay_chain.leaves().await.unwrap(), vec![a5, b4]);
+			// Served from the cache.
+			assert_eq!(select_relay_chain.leaves().await.unwrap(), vec![a5, b4]);
+			// Observes a new best leaf and refreshes the cache.
+			assert_eq!(select_relay_chain.best_chain().await.unwrap().number, 4);
+			assert_eq!(select_relay_chain.leaves().await.unwrap(), vec![b4, a5]);
+		};
+		let overseer_process = async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::Leaves(tx)) => {
+					tx.send(vec![a5, b4]).unwrap();
+				}
+			);
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::Leaves(tx)) => {
+					tx.send(vec![b4, a5]).unwrap();
+				}
+			);
+			virtual_overseer
+		};
+
+		let (_, mut virtual_overseer) = future::join(selection_process, overseer_process).await;
+		assert!(overseer_recv_with_timeout(&mut virtual_overseer, Duration::from_millis(100)).await.is_none());
+	});
+}
+
+#[test]
+fn forced_finality_target_overrides_chain_selection() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let case_vars = chain_0();
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(case_vars.chain.clone()),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	let target_hash = case_vars.target_block;
+	futures::executor::block_on(async move {
+		// The override is shared among clones.
+		select_relay_chain.clone().force_finality_target(A3);
+		assert_eq!(select_relay_chain.finality_target(target_hash, None).await.unwrap(), Some(A3));
+		assert!(overseer_recv_with_timeout(&mut virtual_overseer, Duration::from_millis(100)).await.is_none());
+
+		// A forced target above the maximum number or off the target's chain is ignored.
+		select_relay_chain.force_finality_target(B3);
+		for (target_hash, maybe_max_number) in vec![(A1, Some(3)), (A2, None)] {
+			let selection_process = select_relay_chain.finality_target(target_hash, maybe_max_number);
+			let overseer_process = async {
+				assert_matches!(
+					overseer_recv(&mut virtual_overseer).await,
+					AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(_, tx)) => {
+						tx.send(None).unwrap();
+					}
+				);
+			};
+
+			let (best, _) = future::join(selection_process, overseer_process).await;
+			assert_eq!(best.unwrap(), Some(target_hash));
+		}
+
+		select_relay_chain.clear_forced_finality_target();
+		let selection_process = select_relay_chain.finality_target(target_hash, None);
+		let overseer_process = async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(_, tx)) => {
+					tx.send(None).unwrap();
+				}
+			);
+		};
+
+		let (best, _) = future::join(selection_process, overseer_process).await;
+		assert_eq!(best.unwrap(), Some(target_hash));
+	});
+}
+
+#[test]
+fn fallback_is_used_while_overseer_is_disconnected() {
+	use polkadot_test_client::{
+		ClientBlockImportExt, DefaultTestClientBuilderExt, InitPolkadotBlockBuilder, TestClientBuilder,
+		TestClientBuilderExt,
+	};
+	use sp_blockchain::HeaderBackend;
+
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let mut client = Arc::new(builder.build());
+
+	let select_chain = SelectRelayChainWithFallback::new(
+		backend,
+		polkadot_overseer::Handle::new_disconnected(),
+		Default::default(),
+		Default::default(),
+	);
+
+	let genesis_hash = client.info().genesis_hash;
+	let block = client.init_polkadot_block_builder().build().unwrap().block;
+	let block_hash = block.hash();
+	futures::executor::block_on(client.import(consensus_common::BlockOrigin::Own, block)).unwrap();
+
+	futures::executor::block_on(async move {
+		assert_eq!(select_chain.leaves().await.unwrap(), vec![block_hash]);
+		assert_eq!(select_chain.best_chain().await.unwrap().hash(), block_hash);
+		assert_eq!(select_chain.finality_target(genesis_hash, None).await.unwrap(), Some(block_hash));
+	});
+}
+
+#[test]
+fn ancestors_of_walks_back_to_max_depth() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, _virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain_0().chain),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	let a4 = Hash::repeat_byte(0xA4);
+	assert_eq!(SelectChainExt::ancestors_of(&select_relay_chain, A5, 3).unwrap(), vec![A2, A3, a4]);
+	assert_eq!(select_relay_chain.ancestors_of(A5, 0).unwrap(), Vec::<Hash>::new());
+	// The walk stops at the genesis block.
+	assert_eq!(select_relay_chain.ancestors_of(A2, 10).unwrap(), vec![ChainBuilder::GENESIS_HASH, A1]);
+	assert!(select_relay_chain.ancestors_of(Hash::repeat_byte(0xCC), 1).is_err());
+}
+
+#[test]
+fn block_header_is_looked_up() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, _virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let chain = chain_0().chain;
+	let a3 = chain.header(A3).unwrap().unwrap();
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	assert_eq!(select_relay_chain.block_header(A3).unwrap(), a3);
+	assert_matches!(
+		select_relay_chain.block_header(Hash::repeat_byte(0xCC)),
+		Err(ConsensusError::ChainLookup(_))
+	);
+}
+
+#[test]
+fn block_number_is_looked_up() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, _virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain_0().chain),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	assert_eq!(select_relay_chain.block_number(ChainBuilder::GENESIS_HASH).unwrap(), 0);
+	assert_eq!(select_relay_chain.block_number(B3).unwrap(), 3);
+	assert_matches!(
+		select_relay_chain.block_number(Hash::repeat_byte(0xCC)),
+		Err(ConsensusError::ChainLookup(_))
+	);
+}
+
+#[test]
+fn best_chain_containing_returns_the_best_leaf_header() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain_0().chain),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	futures::executor::block_on(async move {
+		let selection_process = async move {
+			let best = select_relay_chain.best_chain_containing(A1).await.unwrap();
+			assert_eq!(best.map(|header| header.number), Some(5));
+			assert_eq!(select_relay_chain.best_chain_containing(B2).await.unwrap(), None);
+		};
+		let overseer_process = async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(hash, tx)) => {
+					assert_eq!(hash, A1);
+					tx.send(Some(A5)).unwrap();
+				}
+			);
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(hash, tx)) => {
+					assert_eq!(hash, B2);
+					tx.send(None).unwrap();
+				}
+			);
+		};
+
+		future::join(selection_process, overseer_process).await;
+	});
+}
+
+#[test]
+fn try_send_msg_sends_without_an_executor() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let mut sender = context.sender().clone();
+	let (tx, _rx) = oneshot::channel();
+	assert!(OverseerHandleT::try_send_msg(&mut sender, ChainSelectionMessage::Leaves(tx), "test"));
+
+	futures::executor::block_on(async move {
+		assert_matches!(
+			overseer_recv(&mut virtual_overseer).await,
+			AllMessages::ChainSelection(ChainSelectionMessage::Leaves(_))
+		);
+	});
+}
+
+#[test]
+fn try_send_msg_fails_on_disconnected_handle() {
+	let mut handle = polkadot_overseer::Handle::new_disconnected();
+	let (tx, _rx) = oneshot::channel();
+	assert!(!OverseerHandleT::try_send_msg(&mut handle, ChainSelectionMessage::Leaves(tx), "test"));
+}
+
+/// Metrics which only count how often chain selection returned no leaves.
+#[derive(Debug, Default, Clone)]
+struct EmptyLeavesMetrics(Arc<std::sync::atomic::AtomicUsize>);
+
+impl SelectChainMetricsT for EmptyLeavesMetrics {
+	fn note_approval_checking_finality_lag(&self, _lag: BlockNumber) {}
+
+	fn note_disputes_finality_lag(&self, _lag: BlockNumber) {}
+
+	fn on_finality_target_timeout(&self, _request: &'static str) {}
+
+	fn on_forced_finality_target(&self) {}
+
+	fn on_empty_leaves(&self) {
+		self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+	}
+	fn time_finality_target(&self) -> Option<polkadot_node_subsystem_util::metrics::prometheus::prometheus::HistogramTimer> {
+		None
+	}
+
+	fn on_slow_finality_warning(&self) {}
+
+	fn note_overseer_msg_bytes(&self, _bytes: usize) {}
+}
+
+#[test]
+fn empty_leaves_are_reported() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let metrics = EmptyLeavesMetrics::default();
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, EmptyLeavesMetrics>::new(
+		Arc::new(chain_0().chain),
+		context.sender().clone(),
+		metrics.clone(),
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	futures::executor::block_on(async move {
+		let selection_process = async move {
+			assert!(select_relay_chain.best_chain().await.is_err());
+		};
+		let overseer_process = async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ChainSelection(ChainSelectionMessage::Leaves(tx)) => {
+					tx.send(Vec::new()).unwrap();
+				}
+			);
+		};
+
+		future::join(selection_process, overseer_process).await;
+	});
+
+	assert_eq!(metrics.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[test]
+fn walk_backwards_to_target_block_finds_ancestor() {
+	let chain = chain_0().chain;
+
+	let a5 = chain.header(A5).unwrap().unwrap();
+	assert_eq!(
+		crate::grandpa_support::walk_backwards_to_target_block::<Block, _>(&chain, 3, &a5).unwrap(),
+		(A3, 3),
+	);
+
+	// Walking back along a fork ends up on the common ancestor.
+	let b4 = chain.header(Hash::repeat_byte(0xB4)).unwrap().unwrap();
+	assert_eq!(
+		crate::grandpa_support::walk_backwards_to_target_block::<Block, _>(&chain, 2, &b4).unwrap(),
+		(B2, 2),
+	);
+	assert_eq!(
+		crate::grandpa_support::walk_backwards_to_target_block::<Block, _>(&chain, 1, &b4).unwrap(),
+		(A1, 1),
+	);
+}
+
+#[test]
+fn walk_backwards_to_target_block_above_tip_fails() {
+	let chain = chain_0().chain;
+
+	let a5 = chain.header(A5).unwrap().unwrap();
+	assert!(crate::grandpa_support::walk_backwards_to_target_block::<Block, _>(&chain, 6, &a5).is_err());
+}
+
+#[test]
+fn is_approved_checks_the_approved_ancestor() {
+	let pool = sp_core::testing::TaskExecutor::new();
+	let (mut context, mut virtual_overseer) =
+		test_helpers::make_subsystem_context::<ApprovalVotingMessage, _>(pool);
+
+	let chain = chain_0().chain;
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, TestSubsystemSender, NoopMetrics>::new(
+		Arc::new(chain.clone()),
+		context.sender().clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	futures::executor::block_on(async move {
+		let selection_process = async move {
+			assert!(select_relay_chain.is_approved(ChainBuilder::GENESIS_HASH).await.unwrap());
+			assert!(select_relay_chain.is_approved(A3).await.unwrap());
+			assert!(!select_relay_chain.is_approved(A5).await.unwrap());
+		};
+		let overseer_process = async move {
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, number, tx)) => {
+					assert_eq!((hash, number), (A3, 2));
+					tx.send(chain.highest_approved_ancestors(number, hash)).unwrap();
+				}
+			);
+			assert_matches!(
+				overseer_recv(&mut virtual_overseer).await,
+				AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, number, tx)) => {
+					assert_eq!((hash, number), (A5, 4));
+					tx.send(chain.highest_approved_ancestors(number, hash)).unwrap();
+				}
+			);
+		};
+
+		future::join(selection_process, overseer_process).await;
+	});
+}
+
+#[test]
+fn recording_overseer_handle_records_finality_target_requests() {
+	use super::relay_chain_selection::test_helpers::{CannedResponses, RecordingOverseerHandle};
+
+	let overseer = RecordingOverseerHandle::new(CannedResponses {
+		best_leaf_containing: Some(A5),
+		..Default::default()
+	});
+
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, RecordingOverseerHandle, NoopMetrics>::new(
+		Arc::new(chain_0().chain),
+		overseer.clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	// Without an approved ancestor the target itself is finalized, without asking
+	// the dispute coordinator.
+	let target = futures::executor::block_on(select_relay_chain.finality_target(A1, None)).unwrap();
+	assert_eq!(target, Some(A1));
+
+	let messages = overseer.take_messages();
+	assert_eq!(messages.len(), 2);
+	assert_matches!(
+		&messages[0],
+		AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(hash, _)) if *hash == A1
+	);
+	assert_matches!(
+		&messages[1],
+		AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(hash, 1, _)) if *hash == A5
+	);
+	assert!(overseer.take_messages().is_empty());
+}
+
+/// An overseer handle which answers with canned responses, except for the requests it is told
+/// to fail, which it drops as though they could not be sent.
+#[derive(Clone)]
+struct FailingOverseerHandle {
+	inner: relay_chain_selection::test_helpers::RecordingOverseerHandle,
+	fail: fn(&AllMessages) -> bool,
+}
+
+#[async_trait::async_trait]
+impl OverseerHandleT for FailingOverseerHandle {
+	async fn send_msg<M: Send + Into<AllMessages>>(&mut self, msg: M, origin: &'static str) {
+		let msg = msg.into();
+		if !(self.fail)(&msg) {
+			self.inner.send_msg(msg, origin).await
+		}
+	}
+}
+
+#[test]
+fn failed_overseer_requests_are_surfaced() {
+	use super::relay_chain_selection::test_helpers::{CannedResponses, RecordingOverseerHandle};
+
+	let select_relay_chain = |fail: fn(&AllMessages) -> bool| {
+		let overseer = FailingOverseerHandle {
+			inner: RecordingOverseerHandle::new(CannedResponses {
+				leaves: vec![A5],
+				best_leaf_containing: Some(A5),
+				approved_ancestor: Some(HighestApprovedAncestorBlock {
+					hash: A2,
+					number: 2,
+					descriptions: vec![BlockDescription {
+						block_hash: A2,
+						session: 1,
+						candidates: vec![],
+					}],
+				}),
+				..Default::default()
+			}),
+			fail,
+		};
+
+		SelectRelayChain::<TestChainStorage, FailingOverseerHandle, NoopMetrics>::new(
+			Arc::new(chain_0().chain),
+			overseer,
+			NoopMetrics,
+			DEFAULT_FINALITY_TARGET_TIMEOUT,
+			DEFAULT_MAX_FINALITY_LAG,
+			DEFAULT_LEAVES_CACHE_TTL,
+		)
+	};
+
+	let assert_surfaced = |result: Result<(), ConsensusError>, request: &str| {
+		assert_matches!(
+			result,
+			Err(ConsensusError::Other(e)) if e.to_string().contains(request),
+			"failure of {} was not surfaced",
+			request,
+		);
+	};
+
+	futures::executor::block_on(async {
+		let chain_selection = select_relay_chain(|msg| matches!(
+			msg,
+			AllMessages::ChainSelection(ChainSelectionMessage::Leaves(_)),
+		));
+		assert_surfaced(chain_selection.leaves().await.map(|_| ()), "Leaves");
+		assert_surfaced(chain_selection.best_chain().await.map(|_| ()), "Leaves");
+
+		let chain_selection = select_relay_chain(|msg| matches!(
+			msg,
+			AllMessages::ChainSelection(ChainSelectionMessage::BestLeafContaining(..)),
+		));
+		assert_surfaced(chain_selection.finality_target(A1, None).await.map(|_| ()), "BestLeafContaining");
+		assert_surfaced(chain_selection.best_chain_containing(A1).await.map(|_| ()), "BestLeafContaining");
+
+		let chain_selection = select_relay_chain(|msg| matches!(
+			msg,
+			AllMessages::ApprovalVoting(ApprovalVotingMessage::ApprovedAncestor(..)),
+		));
+		assert_surfaced(chain_selection.finality_target(A1, None).await.map(|_| ()), "ApprovedAncestor");
+		assert_surfaced(chain_selection.is_approved(A3).await.map(|_| ()), "ApprovedAncestor");
+
+		let chain_selection = select_relay_chain(|msg| matches!(
+			msg,
+			AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::DetermineUndisputedChain { .. }),
+		));
+		assert_surfaced(chain_selection.finality_target(A1, None).await.map(|_| ()), "DetermineUndisputedChain");
+	});
+}
+
+#[test]
+fn finality_lag_subscriber_receives_lags_in_order() {
+	use super::relay_chain_selection::test_helpers::{CannedResponses, RecordingOverseerHandle};
+
+	let overseer = RecordingOverseerHandle::new(CannedResponses {
+		best_leaf_containing: Some(A5),
+		..Default::default()
+	});
+
+	let select_relay_chain = SelectRelayChain::<TestChainStorage, RecordingOverseerHandle, NoopMetrics>::new(
+		Arc::new(chain_0().chain),
+		overseer.clone(),
+		NoopMetrics,
+		DEFAULT_FINALITY_TARGET_TIMEOUT,
+		DEFAULT_MAX_FINALITY_LAG,
+		DEFAULT_LEAVES_CACHE_TTL,
+	);
+
+	let (tx, mut rx) = futures::channel::mpsc::channel(4);
+	select_relay_chain.subscribe_finality_lag(tx);
+
+	futures::executor::block_on(async {
+		// Nothing approved: both lags reach from the best leaf down to the target.
+		select_relay_chain.finality_target(A1, None).await.unwrap();
+
+		// A3 approved, but only A2 undisputed.
+		let block_description = |block_hash| BlockDescription {
+			block_hash,
+			session: 1,
+			candidates: vec![],
+		};
+		overseer.set_responses(CannedResponses {
+			best_leaf_containing: Some(A5),
+			approved_ancestor: Some(HighestApprovedAncestorBlock {
+				hash: A3,
+				number: 3,
+				descriptions: vec![block_description(A2), block_description(A3)],
+			}),
+			undisputed_chain: Some((2, A2)),
+			..Default::default()
+		});
+		select_relay_chain.finality_target(A1, None).await.unwrap();
+
+		assert_eq!(rx.next().await, Some((4, 4)));
+		assert_eq!(rx.next().await, Some((2, 3)));
+
+		// A closed receiver drops the subscription without affecting `finality_target`.
+		drop(rx);
+		assert_eq!(select_relay_chain.finality_target(A1, None).await.unwrap(), Some(A2));
+	});
+}
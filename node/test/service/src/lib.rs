@@ -84,6 +84,8 @@ pub fn new_full(
 		None,
 		true,
 		None,
+		Default::default(),
+		None,
 		None,
 		worker_program_path,
 		polkadot_service::RealOverseerGen,
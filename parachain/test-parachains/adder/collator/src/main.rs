@@ -64,6 +64,8 @@ fn main() -> Result<()> {
 							None,
 							true,
 							None,
+							Default::default(),
+							None,
 							None,
 							polkadot_service::RealOverseerGen,
 						).map_err(|e| e.to_string())?;
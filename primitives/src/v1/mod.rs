@@ -873,6 +873,7 @@ impl ApprovalVote {
 
 sp_api::decl_runtime_apis! {
 	/// The API for querying the state of parachains on-chain.
+	#[api_version(2)]
 	pub trait ParachainHost<H: Decode = Hash, N: Encode + Decode = BlockNumber> {
 		/// Get the current validators.
 		fn validators() -> Vec<ValidatorId>;
@@ -930,6 +931,39 @@ sp_api::decl_runtime_apis! {
 
 		/// Get the validation code from its hash.
 		fn validation_code_by_hash(hash: ValidationCodeHash) -> Option<ValidationCode>;
+
+		// The methods below were added in version 2 of the API. Callers must check the runtime
+		// supports that version before calling them.
+
+		/// Get the weight consumed by processing backed candidates in the paras inherent of the block.
+		fn last_backed_candidate_weight() -> u64;
+
+		/// Simulate the paras inherent with the given data without persisting any state changes.
+		///
+		/// Returns the weight the inherent would consume, or the error it would fail with.
+		fn dry_run_inherent(data: InherentData) -> Result<u64, runtime_primitives::DispatchError>;
+
+		/// Get the number of candidates which are currently pending availability.
+		fn pending_availability_count() -> u32;
+
+		/// Get the indices of the active validators which did not submit an availability
+		/// bitfield in the block.
+		fn pending_bitfields() -> Vec<ValidatorIndex>;
+
+		/// Get the number of the last block the paras inherent was successfully processed in,
+		/// if any. Light clients can use this to determine staleness without the block history.
+		fn last_included_block_number() -> Option<N>;
+
+		/// Get the number of availability cores which are currently scheduled.
+		fn scheduled_cores_count() -> u32;
+
+		/// Get the para of the candidate pending availability on each availability core,
+		/// or `None` if the core is free.
+		fn availability_core_occupancy() -> Vec<Option<Id>>;
+
+		/// Get the hashes of the candidates backed by the last successfully processed paras
+		/// inherent, for post-mortem analysis of missed parachain slots.
+		fn last_backed_candidate_hashes() -> Vec<CandidateHash>;
 	}
 }
 
@@ -0,0 +1,148 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPCs querying the state of the parachains runtime modules.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use polkadot_primitives::v1::{Block, BlockNumber, Hash, ParachainHost, ValidatorIndex};
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+
+/// The error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// The error code returned when the runtime does not support the runtime API call.
+const UNSUPPORTED_RUNTIME_ERROR: i64 = 2;
+
+/// The first version of the `ParachainHost` runtime API providing the calls used by these RPCs.
+const PARACHAIN_HOST_API_VERSION: u32 = 2;
+
+/// The health of the relay chain selection of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainSelectionHealth {
+	/// Whether chain selection was connected to the overseer when last checked.
+	pub is_connected: bool,
+	/// The number of leaves known to chain selection.
+	pub leaves_count: usize,
+	/// How far behind the best leaf the last finality target was, if any was determined yet.
+	pub last_finality_lag: Option<BlockNumber>,
+}
+
+/// Provides the health of the relay chain selection.
+pub trait ChainSelectionHealthProvider: Send + Sync {
+	/// Get the current health of the relay chain selection.
+	fn chain_selection_health(&self) -> ChainSelectionHealth;
+}
+
+/// Parachain RPC methods.
+#[rpc]
+pub trait ParachainApi<BlockHash> {
+	/// Get the number of candidates which are pending availability at the given block, or at the
+	/// best block if none is given.
+	#[rpc(name = "parachain_pendingAvailabilityCount")]
+	fn pending_availability_count(&self, at: Option<BlockHash>) -> Result<u32>;
+
+	/// Get the indices of the active validators which did not submit an availability bitfield
+	/// in the given block, or in the best block if none is given.
+	#[rpc(name = "parachain_pendingBitfields")]
+	fn pending_bitfields(&self, at: Option<BlockHash>) -> Result<Vec<ValidatorIndex>>;
+
+	/// Get the health of the relay chain selection, if the node runs it.
+	///
+	/// This is not part of `system_health`: that response is Substrate's fixed `Health` type,
+	/// answered by the network worker, and the RPC extensions are built before the network.
+	#[rpc(name = "parachain_chainSelectionHealth")]
+	fn chain_selection_health(&self) -> Result<Option<ChainSelectionHealth>>;
+}
+
+/// Implements the [`ParachainApi`] RPC trait by querying the runtime.
+pub struct Parachain<C> {
+	client: Arc<C>,
+	chain_selection: Option<Arc<dyn ChainSelectionHealthProvider>>,
+}
+
+impl<C> Parachain<C> {
+	/// Create a new instance querying the given client and, if any, the relay chain selection.
+	pub fn new(client: Arc<C>, chain_selection: Option<Arc<dyn ChainSelectionHealthProvider>>) -> Self {
+		Parachain { client, chain_selection }
+	}
+}
+
+impl<C> Parachain<C> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ParachainHost<Block>,
+{
+	/// Resolve the block to query and ensure its runtime supports the calls made by these RPCs.
+	fn runtime_at(&self, at: Option<Hash>) -> Result<BlockId<Block>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let supported = self.client.runtime_api()
+			.has_api_with::<dyn ParachainHost<Block>, _>(&at, |v| v >= PARACHAIN_HOST_API_VERSION)
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Unable to query the runtime API version.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+		if supported {
+			Ok(at)
+		} else {
+			Err(Error {
+				code: ErrorCode::ServerError(UNSUPPORTED_RUNTIME_ERROR),
+				message: format!(
+					"The runtime does not support version {} of the parachain host API.",
+					PARACHAIN_HOST_API_VERSION,
+				),
+				data: None,
+			})
+		}
+	}
+}
+
+impl<C> ParachainApi<Hash> for Parachain<C> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ParachainHost<Block>,
+{
+	fn pending_availability_count(&self, at: Option<Hash>) -> Result<u32> {
+		let at = self.runtime_at(at)?;
+
+		self.client.runtime_api().pending_availability_count(&at).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query the number of candidates pending availability.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn pending_bitfields(&self, at: Option<Hash>) -> Result<Vec<ValidatorIndex>> {
+		let at = self.runtime_at(at)?;
+
+		self.client.runtime_api().pending_bitfields(&at).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query the validators with pending bitfields.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn chain_selection_health(&self) -> Result<Option<ChainSelectionHealth>> {
+		Ok(self.chain_selection.as_ref().map(|chain_selection| chain_selection.chain_selection_health()))
+	}
+}
@@ -1112,7 +1112,44 @@ impl parachains_hrmp::Config for Runtime {
 	type Currency = Balances;
 }
 
-impl parachains_paras_inherent::Config for Runtime {}
+parameter_types! {
+	pub const MaxCodeUpgradesPerInherent: u32 = 1;
+	pub const MaxBackedCandidatesPerPara: u32 = 1;
+	pub const MaxDisputeStatementsPerBlock: u32 = 5_000;
+	pub const ParasInherentWeightRefundPolicy: parachains_paras_inherent::WeightRefundPolicy =
+		parachains_paras_inherent::WeightRefundPolicy::AlwaysRefund;
+	pub const ParasInherentTestMode: bool = false;
+}
+
+impl parachains_paras_inherent::Config for Runtime {
+	type Event = Event;
+	type MaxCodeUpgradesPerInherent = MaxCodeUpgradesPerInherent;
+	type MaxBackedCandidatesPerPara = MaxBackedCandidatesPerPara;
+	type MaxDisputeStatementsPerBlock = MaxDisputeStatementsPerBlock;
+	type WeightInfo = ();
+	type WeightRefundPolicy = ParasInherentWeightRefundPolicy;
+	type MissingInherentHandler = ();
+	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
+	type BackingRewardHandler = ();
+	type CandidateFilter = ();
+	type TestMode = ParasInherentTestMode;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl parachains_paras_inherent::BenchmarkHelper for Runtime {
+	const MAX_BACKED_CANDIDATES: u32 = 50;
+
+	fn inherent_data(backed_candidates: u32) -> primitives::v1::InherentData<Header> {
+		runtime_parachains::test_helpers::benchmark_inherent_data::<Runtime>(
+			Self::MAX_BACKED_CANDIDATES,
+			backed_candidates,
+		)
+	}
+
+	fn fresh_dispute(index: u32, session: SessionIndex) -> primitives::v1::DisputeStatementSet {
+		runtime_parachains::test_helpers::benchmark_fresh_dispute::<Runtime>(index, session)
+	}
+}
 
 impl parachains_scheduler::Config for Runtime {}
 
@@ -1476,7 +1513,7 @@ construct_runtime! {
 		Configuration: parachains_configuration::{Pallet, Call, Storage, Config<T>} = 51,
 		ParasShared: parachains_shared::{Pallet, Call, Storage} = 52,
 		ParaInclusion: parachains_inclusion::{Pallet, Call, Storage, Event<T>} = 53,
-		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent} = 54,
+		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent, Event} = 54,
 		ParasScheduler: parachains_scheduler::{Pallet, Call, Storage} = 55,
 		Paras: parachains_paras::{Pallet, Call, Storage, Event, Config} = 56,
 		Initializer: parachains_initializer::{Pallet, Call, Storage} = 57,
@@ -1676,6 +1713,38 @@ sp_api::impl_runtime_apis! {
 		fn validation_code_by_hash(hash: ValidationCodeHash) -> Option<ValidationCode> {
 			parachains_runtime_api_impl::validation_code_by_hash::<Runtime>(hash)
 		}
+
+		fn last_backed_candidate_weight() -> u64 {
+			parachains_runtime_api_impl::last_backed_candidate_weight::<Runtime>()
+		}
+
+		fn dry_run_inherent(data: primitives::v1::InherentData) -> Result<u64, sp_runtime::DispatchError> {
+			parachains_runtime_api_impl::dry_run_inherent::<Runtime>(data)
+		}
+
+		fn pending_availability_count() -> u32 {
+			parachains_runtime_api_impl::pending_availability_count::<Runtime>()
+		}
+
+		fn pending_bitfields() -> Vec<ValidatorIndex> {
+			parachains_runtime_api_impl::pending_bitfields::<Runtime>()
+		}
+
+		fn last_included_block_number() -> Option<BlockNumber> {
+			parachains_runtime_api_impl::last_included_block_number::<Runtime>()
+		}
+
+		fn scheduled_cores_count() -> u32 {
+			parachains_runtime_api_impl::scheduled_cores_count::<Runtime>()
+		}
+
+		fn availability_core_occupancy() -> Vec<Option<ParaId>> {
+			parachains_runtime_api_impl::availability_core_occupancy::<Runtime>()
+		}
+
+		fn last_backed_candidate_hashes() -> Vec<primitives::v1::CandidateHash> {
+			parachains_runtime_api_impl::last_backed_candidate_hashes::<Runtime>()
+		}
 	}
 
 	impl beefy_primitives::BeefyApi<Block> for Runtime {
@@ -1886,6 +1955,7 @@ sp_api::impl_runtime_apis! {
 			add_benchmark!(params, batches, runtime_common::claims, Claims);
 			add_benchmark!(params, batches, runtime_common::slots, Slots);
 			add_benchmark!(params, batches, runtime_common::paras_registrar, Registrar);
+			add_benchmark!(params, batches, runtime_parachains::paras_inherent, ParasInherent);
 			// Substrate
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_bounties, Bounties);
@@ -0,0 +1,31 @@
+//! Feeds arbitrary SCALE-encoded inherent data into `paras_inherent::enter` of the test runtime.
+//!
+//! The inherent data is decoded from the block body, so no input may make `enter` panic. A
+//! missing inherent panics in `on_finalize` by design, which is why the block is never finalized.
+
+use honggfuzz::fuzz;
+use parity_scale_codec::Decode;
+use polkadot_test_runtime::{GenesisConfig, ParasInherent, System};
+use primitives::v1::{Header, InherentData};
+use sp_runtime::{traits::Header as HeaderT, BuildStorage};
+
+fn main() {
+	let storage = GenesisConfig::default().build_storage().unwrap();
+
+	loop {
+		fuzz!(|data: &[u8]| {
+			let inherent_data = match InherentData::<Header>::decode(&mut &data[..]) {
+				Ok(inherent_data) => inherent_data,
+				Err(_) => return,
+			};
+
+			sp_io::TestExternalities::new(storage.clone()).execute_with(|| {
+				// make the parent header check pass, so the input reaches the actual processing.
+				System::set_block_number(1);
+				System::set_parent_hash(inherent_data.parent_header.hash());
+
+				let _ = ParasInherent::enter(frame_system::RawOrigin::None.into(), inherent_data);
+			});
+		});
+	}
+}
@@ -0,0 +1,61 @@
+//! Writes the inherent data used by the `paras_inherent` unit tests into the corpus of the
+//! `paras_inherent_enter` fuzzer.
+//!
+//! Run this from the fuzzer directory before `cargo hfuzz run paras_inherent_enter`.
+
+use parity_scale_codec::Encode;
+use primitives::v1::{
+	AvailabilityBitfield, BackedCandidate, Header, InherentData, UncheckedSigned,
+	UncheckedSignedAvailabilityBitfield, ValidatorIndex,
+};
+
+const CORPUS: &str = "hfuzz_workspace/paras_inherent_enter/input";
+
+fn default_header() -> Header {
+	Header {
+		parent_hash: Default::default(),
+		number: 0,
+		state_root: Default::default(),
+		extrinsics_root: Default::default(),
+		digest: Default::default(),
+	}
+}
+
+fn unchecked_bitfield(validator_index: u32) -> UncheckedSignedAvailabilityBitfield {
+	UncheckedSigned::new(
+		AvailabilityBitfield(Default::default()),
+		ValidatorIndex(validator_index),
+		sp_core::sr25519::Signature([0u8; 64]).into(),
+	)
+}
+
+fn main() {
+	let seeds = vec![
+		// an empty inherent, as in `dry_run_enter`.
+		InherentData {
+			bitfields: Vec::new(),
+			backed_candidates: Vec::new(),
+			disputes: Vec::new(),
+			parent_header: default_header(),
+		},
+		// more bitfields than validators, as in `rejects_more_bitfields_than_validators`.
+		InherentData {
+			bitfields: vec![unchecked_bitfield(0), unchecked_bitfield(1)],
+			backed_candidates: Vec::new(),
+			disputes: Vec::new(),
+			parent_header: default_header(),
+		},
+		// many backed candidates, as in the `paras_inherent_weight` tests.
+		InherentData {
+			bitfields: Vec::new(),
+			backed_candidates: vec![BackedCandidate::default(); 10],
+			disputes: Vec::new(),
+			parent_header: default_header(),
+		},
+	];
+
+	std::fs::create_dir_all(CORPUS).unwrap();
+	for (i, seed) in seeds.iter().enumerate() {
+		std::fs::write(format!("{}/unit_test_{}", CORPUS, i), seed.encode()).unwrap();
+	}
+}
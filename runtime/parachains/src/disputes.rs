@@ -121,6 +121,11 @@ pub trait DisputesHandler<BlockNumber> {
 	/// or concluded dispute with supermajority-against.
 	fn could_be_invalid(session: SessionIndex, candidate_hash: CandidateHash) -> bool;
 
+	/// Whether any of the given candidates could be invalid. See [`Self::could_be_invalid`].
+	fn any_could_be_invalid(session: SessionIndex, candidate_hashes: &[CandidateHash]) -> bool {
+		candidate_hashes.iter().any(|hash| Self::could_be_invalid(session, *hash))
+	}
+
 	/// Called by the initializer to initialize the configuration module.
 	fn initializer_initialize(now: BlockNumber) -> Weight;
 
@@ -2706,4 +2711,47 @@ mod tests {
 			);
 		})
 	}
+
+	#[test]
+	fn any_could_be_invalid_checks_every_candidate() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let ongoing = CandidateHash(sp_core::H256::repeat_byte(1));
+			let concluded_for = CandidateHash(sp_core::H256::repeat_byte(2));
+			let concluded_against = CandidateHash(sp_core::H256::repeat_byte(3));
+			let undisputed = CandidateHash(sp_core::H256::repeat_byte(4));
+
+			let dispute = |against, concluded_at| DisputeState {
+				validators_for: bitvec![BitOrderLsb0, u8; 0; 4],
+				validators_against: if against {
+					bitvec![BitOrderLsb0, u8; 1; 4]
+				} else {
+					bitvec![BitOrderLsb0, u8; 0; 4]
+				},
+				start: 0,
+				concluded_at,
+			};
+
+			<Disputes<Test>>::insert(&1, &ongoing, dispute(false, None));
+			<Disputes<Test>>::insert(&1, &concluded_for, dispute(false, Some(1)));
+			<Disputes<Test>>::insert(&1, &concluded_against, dispute(true, Some(1)));
+
+			let any_could_be_invalid = |hashes: &[CandidateHash]| {
+				<Pallet<Test> as DisputesHandler<BlockNumber>>::any_could_be_invalid(1, hashes)
+			};
+
+			assert!(!any_could_be_invalid(&[]));
+			assert!(!any_could_be_invalid(&[undisputed]));
+			assert!(!any_could_be_invalid(&[concluded_for]));
+			assert!(!any_could_be_invalid(&[undisputed, concluded_for]));
+			assert!(any_could_be_invalid(&[undisputed, ongoing]));
+			assert!(any_could_be_invalid(&[concluded_against]));
+			assert!(any_could_be_invalid(&[concluded_for, concluded_against]));
+
+			// The default handler has no disputes.
+			assert!(!<() as DisputesHandler<BlockNumber>>::any_could_be_invalid(1, &[ongoing]));
+
+			// Disputes of other sessions are not considered.
+			assert!(!<Pallet<Test> as DisputesHandler<BlockNumber>>::any_could_be_invalid(2, &[ongoing]));
+		})
+	}
 }
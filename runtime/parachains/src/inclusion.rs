@@ -786,6 +786,36 @@ impl<T: Config> Pallet<T> {
 		cleaned_up_cores
 	}
 
+	/// The number of candidates which are currently pending availability.
+	pub(crate) fn pending_availability_count() -> u32 {
+		<PendingAvailability<T>>::iter_keys().count() as u32
+	}
+
+	/// The cores occupied by candidates pending availability and the paras of these candidates,
+	/// sorted by core.
+	pub(crate) fn occupied_cores() -> Vec<(CoreIndex, ParaId)> {
+		let mut occupied: Vec<_> = <PendingAvailability<T>>::iter()
+			.map(|(para_id, candidate)| (candidate.core, para_id))
+			.collect();
+
+		// `PendingAvailability` is iterated in hashed key order.
+		occupied.sort_unstable();
+		occupied
+	}
+
+	/// The indices of the active validators which did not submit a bitfield in this block.
+	pub(crate) fn pending_bitfields() -> Vec<ValidatorIndex> {
+		let now = <frame_system::Pallet<T>>::block_number();
+		let n_validators = shared::Pallet::<T>::active_validator_keys().len();
+
+		(0..n_validators)
+			.map(|i| ValidatorIndex(i as _))
+			.filter(|validator_index| <AvailabilityBitfields<T>>::get(validator_index)
+				.map_or(true, |record| record.submitted_at != now)
+			)
+			.collect()
+	}
+
 	/// Forcibly enact the candidate with the given ID as though it had been deemed available
 	/// by bitfields.
 	///
@@ -1194,6 +1224,27 @@ mod tests {
 		Some(persisted_validation_data.hash())
 	}
 
+	#[test]
+	fn pending_bitfields_are_those_not_submitted_in_this_block() {
+		let validators = vec![Sr25519Keyring::Alice, Sr25519Keyring::Bob, Sr25519Keyring::Charlie];
+
+		new_test_ext(genesis_config(Vec::new())).execute_with(|| {
+			shared::Pallet::<Test>::set_active_validators_ascending(validator_pubkeys(&validators));
+			System::set_block_number(2);
+
+			<AvailabilityBitfields<Test>>::insert(&ValidatorIndex(0), AvailabilityBitfieldRecord {
+				bitfield: default_bitfield(),
+				submitted_at: 2,
+			});
+			<AvailabilityBitfields<Test>>::insert(&ValidatorIndex(1), AvailabilityBitfieldRecord {
+				bitfield: default_bitfield(),
+				submitted_at: 1,
+			});
+
+			assert_eq!(ParaInclusion::pending_bitfields(), vec![ValidatorIndex(1), ValidatorIndex(2)]);
+		});
+	}
+
 	#[test]
 	fn collect_pending_cleans_up_pending() {
 		let chain_a = ParaId::from(1);
@@ -1243,6 +1294,50 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn collect_pending_returns_freed_cores() {
+		let chain_a = ParaId::from(1);
+		let chain_b = ParaId::from(2);
+		let thread_a = ParaId::from(3);
+
+		let paras = vec![(chain_a, true), (chain_b, true), (thread_a, false)];
+		new_test_ext(genesis_config(paras.clone())).execute_with(|| {
+			let default_candidate = TestCandidateBuilder::default().build();
+			for (i, (para_id, _)) in paras.into_iter().enumerate() {
+				<PendingAvailability<Test>>::insert(para_id, CandidatePendingAvailability {
+					core: CoreIndex::from(2 - i as u32),
+					hash: default_candidate.hash(),
+					descriptor: default_candidate.descriptor.clone(),
+					availability_votes: default_availability_votes(),
+					relay_parent_number: 0,
+					backed_in_number: 0,
+					backers: default_backing_bitfield(),
+					backing_group: GroupIndex::from(i as u32),
+				});
+				PendingAvailabilityCommitments::<Test>::insert(
+					para_id,
+					default_candidate.commitments.clone(),
+				);
+			}
+
+			run_to_block(5, |_| None);
+			assert_eq!(ParaInclusion::pending_availability_count(), 3);
+			assert_eq!(
+				ParaInclusion::occupied_cores(),
+				vec![
+					(CoreIndex::from(0), thread_a),
+					(CoreIndex::from(1), chain_b),
+					(CoreIndex::from(2), chain_a),
+				],
+			);
+
+			let mut freed = ParaInclusion::collect_pending(|_core, _since| true);
+			freed.sort_unstable();
+			assert_eq!(freed, vec![CoreIndex::from(0), CoreIndex::from(1), CoreIndex::from(2)]);
+			assert_eq!(ParaInclusion::pending_availability_count(), 0);
+		});
+	}
+
 	#[test]
 	fn bitfield_checks() {
 		let chain_a = ParaId::from(1);
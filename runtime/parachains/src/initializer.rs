@@ -274,8 +274,8 @@ impl<T: Config> Pallet<T> {
 	}
 
 	// Allow to trigger on_new_session in tests, this is needed as long as pallet_session is not
-	// implemented in mock.
-	#[cfg(test)]
+	// implemented in mock. Benchmarks use this to enact a session with validators of their own.
+	#[cfg(any(test, feature = "runtime-benchmarks"))]
 	pub(crate) fn test_trigger_on_new_session<'a, I: 'a>(
 		changed: bool,
 		session_index: SessionIndex,
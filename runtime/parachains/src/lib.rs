@@ -43,6 +43,8 @@ mod util;
 
 #[cfg(test)]
 mod mock;
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub mod test_helpers;
 
 pub use origin::{Origin, ensure_parachain};
 use primitives::v1::Id as ParaId;
@@ -22,16 +22,19 @@ use sp_runtime::traits::{
 	BlakeTwo256, IdentityLookup,
 };
 use primitives::v1::{
-	AuthorityDiscoveryId, Balance, BlockNumber, Header, ValidatorIndex, SessionIndex,
+	AuthorityDiscoveryId, BackedCandidate, Balance, BlockNumber, CandidateHash, Header,
+	Id as ParaId, MultiDisputeStatementSet, ValidatorIndex, SessionIndex, PARACHAIN_KEY_TYPE_ID,
 };
 use frame_support::parameter_types;
 use frame_support::traits::GenesisBuild;
 use frame_support_test::TestRandomness;
+use sp_keystore::{KeystoreExt, SyncCryptoStore};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::{
 	inclusion, scheduler, dmp, ump, hrmp, session_info, paras, configuration,
-	initializer, shared, disputes,
+	initializer, shared, disputes, paras_inherent,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -56,6 +59,7 @@ frame_support::construct_runtime!(
 		Hrmp: hrmp::{Pallet, Call, Storage, Event<T>},
 		SessionInfo: session_info::{Pallet, Call, Storage},
 		Disputes: disputes::{Pallet, Storage, Event<T>},
+		ParaInherent: paras_inherent::{Pallet, Call, Storage, Event, Config},
 	}
 );
 
@@ -193,11 +197,163 @@ impl crate::scheduler::Config for Test { }
 
 impl crate::inclusion::Config for Test {
 	type Event = Event;
-	type DisputesHandler = Disputes;
+	type DisputesHandler = TestDisputesHandler;
 	type RewardValidators = TestRewardValidators;
 }
 
-impl crate::paras_inherent::Config for Test { }
+parameter_types! {
+	// dispute data which is left after filtering is rejected with this error.
+	pub static DisputeDataError: Option<sp_runtime::DispatchError> = None;
+}
+
+/// Handles disputes with the disputes pallet, unless `DisputeDataError` is set.
+pub struct TestDisputesHandler;
+
+impl crate::disputes::DisputesHandler<BlockNumber> for TestDisputesHandler {
+	fn is_frozen() -> bool {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::is_frozen()
+	}
+
+	fn filter_multi_dispute_data(statement_sets: &mut MultiDisputeStatementSet) {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::filter_multi_dispute_data(statement_sets)
+	}
+
+	fn provide_multi_dispute_data(
+		statement_sets: MultiDisputeStatementSet,
+	) -> Result<Vec<(SessionIndex, CandidateHash)>, sp_runtime::DispatchError> {
+		if let Some(err) = DisputeDataError::get() {
+			return Err(err)
+		}
+
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::provide_multi_dispute_data(statement_sets)
+	}
+
+	fn note_included(
+		session: SessionIndex,
+		candidate_hash: CandidateHash,
+		included_in: BlockNumber,
+	) {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::note_included(
+			session,
+			candidate_hash,
+			included_in,
+		)
+	}
+
+	fn could_be_invalid(session: SessionIndex, candidate_hash: CandidateHash) -> bool {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::could_be_invalid(session, candidate_hash)
+	}
+
+	fn initializer_initialize(now: BlockNumber) -> frame_support::weights::Weight {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::initializer_initialize(now)
+	}
+
+	fn initializer_finalize() {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::initializer_finalize()
+	}
+
+	fn initializer_on_new_session(notification: &initializer::SessionChangeNotification<BlockNumber>) {
+		<Disputes as disputes::DisputesHandler<BlockNumber>>::initializer_on_new_session(notification)
+	}
+}
+
+parameter_types! {
+	pub static MaxCodeUpgradesPerInherent: u32 = 1;
+	// most tests submit several candidates of the same para, so this is only limited on demand.
+	pub static MaxBackedCandidatesPerPara: u32 = u32::MAX;
+	pub static MaxDisputeStatementsPerBlock: u32 = 1_000;
+	pub static ParasInherentTestMode: bool = false;
+	// candidates of this para are dropped by the paras inherent's candidate filter.
+	pub static RejectedCandidatePara: Option<ParaId> = None;
+	// blocks without the paras inherent panic, as with the default handler, rather than
+	// depositing an event.
+	pub static PanicOnMissingInherent: bool = false;
+	pub static ParasInherentWeightRefundPolicy: crate::paras_inherent::WeightRefundPolicy =
+		crate::paras_inherent::WeightRefundPolicy::AlwaysRefund;
+}
+
+impl crate::paras_inherent::Config for Test {
+	type Event = Event;
+	type MaxCodeUpgradesPerInherent = MaxCodeUpgradesPerInherent;
+	type MaxBackedCandidatesPerPara = MaxBackedCandidatesPerPara;
+	type MaxDisputeStatementsPerBlock = MaxDisputeStatementsPerBlock;
+	type WeightInfo = ();
+	type WeightRefundPolicy = ParasInherentWeightRefundPolicy;
+	type MissingInherentHandler = TestMissingInherentHandler;
+	type FindAuthor = TestFindAuthor;
+	type BackingRewardHandler = TestBackingRewardHandler;
+	type CandidateFilter = TestCandidateFilter;
+	type TestMode = ParasInherentTestMode;
+}
+
+/// Provides the benchmark data for the paras inherent, with validators whose keys are generated
+/// in the keystore of the test externalities.
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::paras_inherent::BenchmarkHelper for Test {
+	const MAX_BACKED_CANDIDATES: u32 = 8;
+
+	fn inherent_data(backed_candidates: u32) -> primitives::v1::InherentData<Header> {
+		crate::test_helpers::benchmark_inherent_data::<Test>(
+			Self::MAX_BACKED_CANDIDATES,
+			backed_candidates,
+		)
+	}
+
+	fn fresh_dispute(index: u32, session: SessionIndex) -> primitives::v1::DisputeStatementSet {
+		crate::test_helpers::benchmark_fresh_dispute::<Test>(index, session)
+	}
+}
+
+/// The author of every block.
+pub const BLOCK_AUTHOR: AccountId = 42;
+
+pub struct TestFindAuthor;
+
+impl frame_support::traits::FindAuthor<AccountId> for TestFindAuthor {
+	fn find_author<'a, I>(_: I) -> Option<AccountId>
+		where I: 'a,
+	{
+		Some(BLOCK_AUTHOR)
+	}
+}
+
+thread_local! {
+	pub static AUTHOR_BACKING_REWARDS: RefCell<Vec<(AccountId, u32)>> = RefCell::new(Vec::new());
+}
+
+pub fn author_backing_rewards() -> Vec<(AccountId, u32)> {
+	AUTHOR_BACKING_REWARDS.with(|r| r.borrow().clone())
+}
+
+pub struct TestBackingRewardHandler;
+
+impl crate::paras_inherent::BackingRewardHandler<AccountId> for TestBackingRewardHandler {
+	fn reward_backed(author: AccountId, count: u32) {
+		AUTHOR_BACKING_REWARDS.with(|r| r.borrow_mut().push((author, count)));
+	}
+}
+
+pub struct TestCandidateFilter;
+
+impl crate::paras_inherent::BackedCandidateFilter<H256> for TestCandidateFilter {
+	fn filter(candidate: &BackedCandidate<H256>) -> bool {
+		RejectedCandidatePara::get() != Some(candidate.descriptor().para_id)
+	}
+}
+
+pub struct TestMissingInherentHandler;
+
+impl crate::paras_inherent::MissingInherentHandler for TestMissingInherentHandler {
+	fn handle() {
+		use crate::paras_inherent::{DepositMissingInherentEvent, MissingInherentHandler};
+
+		if PanicOnMissingInherent::get() {
+			<() as MissingInherentHandler>::handle()
+		} else {
+			DepositMissingInherentEvent::<Test>::handle()
+		}
+	}
+}
 
 impl crate::session_info::Config for Test { }
 
@@ -260,12 +416,26 @@ impl inclusion::RewardValidators for TestRewardValidators {
 pub fn new_test_ext(state: MockGenesisConfig) -> TestExternalities {
 	BACKING_REWARDS.with(|r| r.borrow_mut().clear());
 	AVAILABILITY_REWARDS.with(|r| r.borrow_mut().clear());
+	AUTHOR_BACKING_REWARDS.with(|r| r.borrow_mut().clear());
 
 	let mut t = state.system.build_storage::<Test>().unwrap();
 	state.configuration.assimilate_storage(&mut t).unwrap();
 	GenesisBuild::<Test>::assimilate_storage(&state.paras, &mut t).unwrap();
+	GenesisBuild::<Test>::assimilate_storage(&state.paras_inherent, &mut t).unwrap();
+
+	// the keys of the well-known dev accounts sign as validators in the test helpers.
+	let keystore = sc_keystore::LocalKeystore::in_memory();
+	for validator in keyring::Sr25519Keyring::iter() {
+		SyncCryptoStore::sr25519_generate_new(
+			&keystore,
+			PARACHAIN_KEY_TYPE_ID,
+			Some(&validator.to_seed()),
+		).unwrap();
+	}
 
-	t.into()
+	let mut ext: TestExternalities = t.into();
+	ext.register_extension(KeystoreExt(Arc::new(keystore)));
+	ext
 }
 
 #[derive(Default)]
@@ -273,4 +443,5 @@ pub struct MockGenesisConfig {
 	pub system: frame_system::GenesisConfig,
 	pub configuration: crate::configuration::GenesisConfig<Test>,
 	pub paras: crate::paras::GenesisConfig,
+	pub paras_inherent: crate::paras_inherent::GenesisConfig,
 }
@@ -21,20 +21,20 @@
 //! as it has no initialization logic and its finalization logic depends only on the details of
 //! this module.
 
-use sp_std::prelude::*;
-use sp_runtime::traits::Header as HeaderT;
+use sp_std::{collections::{btree_map::BTreeMap, btree_set::BTreeSet}, prelude::*};
+use sp_runtime::{traits::Header as HeaderT, DispatchError, RuntimeDebug, TransactionOutcome};
+use parity_scale_codec::{Encode, Decode};
 use primitives::v1::{
-	BackedCandidate, PARACHAINS_INHERENT_IDENTIFIER, InherentData as ParachainsInherentData,
+	BackedCandidate, CandidateHash, CoreIndex, Id as ParaId, PARACHAINS_INHERENT_IDENTIFIER,
+	InherentData as ParachainsInherentData, UncheckedSignedAvailabilityBitfield,
 };
 use frame_support::{
-	decl_error, decl_module, decl_storage, ensure,
-	dispatch::DispatchResultWithPostInfo,
-	weights::{DispatchClass, Weight},
-	traits::Get,
+	weights::Weight,
+	traits::{FindAuthor, Get},
 	inherent::{InherentIdentifier, InherentData, MakeFatalError, ProvideInherent},
 };
-use frame_system::ensure_none;
 use crate::{
+	configuration,
 	disputes::DisputesHandler,
 	inclusion,
 	scheduler::{self, FreedReason},
@@ -42,29 +42,236 @@ use crate::{
 	ump,
 };
 
+pub use pallet::*;
+
 const LOG_TARGET: &str = "runtime::inclusion-inherent";
-// In the future, we should benchmark these consts; these are all untested assumptions for now.
-const BACKED_CANDIDATE_WEIGHT: Weight = 100_000;
-const INCLUSION_INHERENT_CLAIMED_WEIGHT: Weight = 1_000_000_000;
-// we assume that 75% of an paras inherent's weight is used processing backed candidates
-const MINIMAL_INCLUSION_INHERENT_WEIGHT: Weight = INCLUSION_INHERENT_CLAIMED_WEIGHT / 4;
+/// The weight of noting the availability of a single erasure chunk, i.e. a set bit of a bitfield.
+///
+/// This is a placeholder until the processing of bitfields is benchmarked.
+const ERASURE_CHUNK_WEIGHT: Weight = 10_000;
+
+/// Weight functions needed for the paras inherent.
+///
+/// The required functions mirror the benchmarks, so a weights module generated by the benchmark
+/// CLI implements this trait as is. The weights used by the pallet are derived from them.
+pub trait WeightInfo {
+	/// The weight of entering the paras inherent with the given number of backed candidates.
+	fn enter_backed_candidates(c: u32) -> Weight;
+	/// The weight of entering the paras inherent with the given number of fresh disputes and no
+	/// backed candidates.
+	fn enter_with_disputes(d: u32) -> Weight;
+
+	/// The weight of entering the paras inherent without any backed candidates or disputes.
+	fn base_weight() -> Weight {
+		Self::enter_backed_candidates(0)
+	}
 
-pub trait Config: inclusion::Config + scheduler::Config {}
+	/// The weight of processing a single backed candidate.
+	fn backed_candidate_weight() -> Weight {
+		Self::enter_backed_candidates(1).saturating_sub(Self::enter_backed_candidates(0))
+	}
 
-decl_storage! {
-	trait Store for Module<T: Config> as ParaInherent {
-		/// Whether the paras inherent was included within this block.
-		///
-		/// The `Option<()>` is effectively a `bool`, but it never hits storage in the `None` variant
-		/// due to the guarantees of FRAME's storage APIs.
-		///
-		/// If this is `None` at the end of the block, we panic and render the block invalid.
-		Included: Option<()>;
+	/// The weight of handling a single dispute statement set.
+	fn dispute_weight() -> Weight {
+		Self::enter_with_disputes(1).saturating_sub(Self::enter_with_disputes(0))
+	}
+}
+
+/// The default weights, used by all runtimes.
+///
+/// These are placeholders rather than benchmarked values. They are to be replaced by a weights
+/// module generated from the `enter_backed_candidates` and `enter_with_disputes` benchmarks.
+// TODO: generate the weights of the paras inherent for the Kusama and Westend runtimes.
+impl WeightInfo for () {
+	fn enter_backed_candidates(c: u32) -> Weight {
+		// we assume that 75% of the claimed weight of 1_000_000_000 is used processing backed
+		// candidates.
+		(250_000_000 as Weight)
+			.saturating_add((100_000 as Weight).saturating_mul(c as Weight))
+	}
+
+	fn enter_with_disputes(d: u32) -> Weight {
+		(250_000_000 as Weight)
+			.saturating_add((1_000_000 as Weight).saturating_mul(d as Weight))
+	}
+}
+
+/// The reason why backed candidates were dropped from a paras inherent.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum TruncationReason {
+	/// Candidates beyond the maximum number of code upgrades per inherent were dropped.
+	CodeUpgrades,
+	/// Candidates were dropped to keep the block within its weight limit.
+	BlockWeight,
+	/// Candidates beyond the maximum number of backed candidates per para were dropped.
+	PerParaLimit,
+}
+
+/// Whether the weight of backed candidates dropped from a paras inherent is refunded.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum WeightRefundPolicy {
+	/// Only charge the weight of the backed candidates which were processed.
+	AlwaysRefund,
+	/// Charge the full weight claimed by the inherent if any backed candidates were dropped,
+	/// penalizing block authors for oversized inherents.
+	NeverRefundOnTruncation,
+}
+
+/// The outcome of processing the backed candidates of a paras inherent.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum InherentOutcome {
+	/// All proposed backed candidates were accepted for processing.
+	Normal {
+		/// The number of backed candidates.
+		backed_count: u32,
+	},
+	/// Some of the proposed backed candidates were dropped before processing.
+	Truncated {
+		/// The number of backed candidates in the inherent data.
+		proposed: u32,
+		/// The number of backed candidates which were accepted for processing.
+		accepted: u32,
+		/// Why the backed candidates were dropped. If several reasons apply, this is the
+		/// last one which was applied.
+		reason: TruncationReason,
+	},
+}
+
+/// Provides the data needed to benchmark the paras inherent.
+///
+/// This is implemented by runtimes, as the data depends on the runtime's validator keys and
+/// scheduling.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper: Config {
+	/// The maximum number of backed candidates `inherent_data` can provide.
+	const MAX_BACKED_CANDIDATES: u32;
+
+	/// Set up the current block, with validators whose keys are known to the helper and paras
+	/// scheduled on availability cores, and get the data of its paras inherent.
+	///
+	/// The data contains a signed availability bitfield of every active validator and the given
+	/// number of backed candidates, each of a distinct para.
+	fn inherent_data(backed_candidates: u32) -> ParachainsInherentData<Self::Header>;
+
+	/// A set of statements opening a fresh dispute in the given session, about a distinct
+	/// candidate for each index.
+	fn fresh_dispute(
+		index: u32,
+		session: primitives::v1::SessionIndex,
+	) -> primitives::v1::DisputeStatementSet;
+}
+
+/// A handler for blocks which are finalized without the paras inherent.
+pub trait MissingInherentHandler {
+	/// Called from `on_finalize` if the paras inherent was not included in the block.
+	fn handle();
+}
+
+/// The default handler, which panics and thereby renders the block invalid.
+impl MissingInherentHandler for () {
+	fn handle() {
+		panic!("Bitfields and heads must be included every block");
+	}
+}
+
+/// A handler which deposits an `InherentMissing` event rather than rendering the block invalid.
+///
+/// Runtimes which want to take further action, e.g. charging the block author, can wrap this.
+pub struct DepositMissingInherentEvent<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Config> MissingInherentHandler for DepositMissingInherentEvent<T> {
+	fn handle() {
+		let event: <T as Config>::Event = Event::InherentMissing.into();
+		<frame_system::Pallet<T>>::deposit_event(event.into());
+	}
+}
+
+/// A runtime-level policy on which backed candidates are processed by the paras inherent.
+pub trait BackedCandidateFilter<H> {
+	/// Whether the candidate should be processed. Candidates for which this returns `false`
+	/// are dropped from the inherent before any other checks.
+	fn filter(candidate: &BackedCandidate<H>) -> bool;
+}
+
+/// The default filter, which accepts all candidates.
+impl<H> BackedCandidateFilter<H> for () {
+	fn filter(_candidate: &BackedCandidate<H>) -> bool {
+		true
 	}
 }
 
-decl_error! {
-	pub enum Error for Module<T: Config> {
+/// A handler crediting block authors for the candidates backed in their blocks.
+pub trait BackingRewardHandler<AccountId> {
+	/// Called at the end of the paras inherent with the author of the block and the number of
+	/// candidates backed in it.
+	fn reward_backed(author: AccountId, count: u32);
+}
+
+/// The default handler, which does not reward block authors.
+impl<AccountId> BackingRewardHandler<AccountId> for () {
+	fn reward_backed(_author: AccountId, _count: u32) {}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use super::*;
+
+	/// The current storage version.
+	pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + inclusion::Config + scheduler::Config {
+		/// The overarching event type.
+		type Event: From<Event> + IsType<<Self as frame_system::Config>::Event>;
+		/// The maximum number of backed candidates containing a code upgrade which are processed
+		/// within a single paras inherent.
+		type MaxCodeUpgradesPerInherent: Get<u32>;
+		/// The maximum number of backed candidates of a single para which are processed within a
+		/// single paras inherent.
+		type MaxBackedCandidatesPerPara: Get<u32>;
+		/// The maximum number of dispute statements, summed over all dispute statement sets,
+		/// which are accepted within a single paras inherent.
+		type MaxDisputeStatementsPerBlock: Get<u32>;
+		/// Weight information for the paras inherent.
+		type WeightInfo: WeightInfo;
+		/// Whether the weight of backed candidates dropped from the inherent is refunded.
+		type WeightRefundPolicy: Get<WeightRefundPolicy>;
+		/// What to do when a block is finalized without the paras inherent.
+		type MissingInherentHandler: MissingInherentHandler;
+		/// Find the author of the current block, to be rewarded for backed candidates.
+		type FindAuthor: FindAuthor<Self::AccountId>;
+		/// How block authors are rewarded for the candidates backed in their blocks.
+		type BackingRewardHandler: BackingRewardHandler<Self::AccountId>;
+		/// Which backed candidates are processed.
+		type CandidateFilter: BackedCandidateFilter<Self::Hash>;
+		/// Whether `try_enter` processes the submitted inherent data. This must only be enabled
+		/// on test networks, as it allows any signed origin to provide the paras inherent.
+		type TestMode: Get<bool>;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event {
+		/// The paras inherent was processed. \[outcome\]
+		InherentProcessed(InherentOutcome),
+		/// A block was finalized without the paras inherent.
+		InherentMissing,
+		/// Backed candidates of a para beyond `MaxBackedCandidatesPerPara` were dropped.
+		/// \[para_id, dropped\]
+		ExcessCandidatesDropped(ParaId, u32),
+		/// Pending upward messages were dispatched by the paras inherent. \[count, weight\]
+		UpwardMessagesProcessed(u32, Weight),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
 		/// Inclusion inherent called more than once per block.
 		TooManyInclusionInherents,
 		/// The hash of the submitted parent header doesn't correspond to the saved block hash of
@@ -72,60 +279,344 @@ decl_error! {
 		InvalidParentHeader,
 		/// Potentially invalid candidate.
 		CandidateCouldBeInvalid,
+		/// There are more signed bitfields than active validators.
+		TooManyBitfields,
+		/// There are more dispute statements than `MaxDisputeStatementsPerBlock`.
+		TooManyDisputeStatements,
+		/// `try_enter` was called while `TestMode` is disabled.
+		TestModeDisabled,
+	}
+
+	impl<T: Config> sp_std::fmt::Display for Error<T> {
+		fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+			let message = match self {
+				Error::TooManyInclusionInherents =>
+					"the paras inherent was included more than once in the block",
+				Error::InvalidParentHeader =>
+					"the parent header does not match the hash of the parent block",
+				Error::CandidateCouldBeInvalid => "a backed candidate is disputed or could be invalid",
+				Error::TooManyBitfields => "there are more signed bitfields than active validators",
+				Error::TooManyDisputeStatements =>
+					"there are more dispute statements than allowed per block",
+				Error::TestModeDisabled => "the paras inherent can only be entered by signed origins in test mode",
+				// the hidden variant added by the pallet macro, which is never constructed.
+				_ => self.as_str(),
+			};
+
+			f.write_str(message)
+		}
+	}
+
+	/// Whether the paras inherent was included within this block.
+	///
+	/// The `Option<()>` is effectively a `bool`, but it never hits storage in the `None` variant
+	/// due to the guarantees of FRAME's storage APIs.
+	///
+	/// If this is `None` at the end of the block, `T::MissingInherentHandler` is invoked, which
+	/// by default panics and renders the block invalid.
+	#[pallet::storage]
+	pub(super) type Included<T: Config> = StorageValue<_, ()>;
+
+	/// The number of the last block the paras inherent was successfully processed in.
+	#[pallet::storage]
+	#[pallet::getter(fn last_included_block_number)]
+	pub(super) type LastIncludedBlockNumber<T: Config> = StorageValue<_, T::BlockNumber>;
+
+	/// The hashes of the candidates backed by the last successfully processed paras inherent.
+	///
+	/// This is a plain `Vec`, as the number of availability cores is only known at runtime. It
+	/// is overwritten with the candidates accepted by `process_candidates`, which backs at most
+	/// one candidate per scheduled core, so it never holds more hashes than there are cores.
+	#[pallet::storage]
+	#[pallet::getter(fn last_backed_candidate_hashes)]
+	pub(super) type LastBackedCandidateHashes<T: Config> = StorageValue<_, Vec<CandidateHash>, ValueQuery>;
+
+	/// The weight consumed by processing backed candidates in this block.
+	///
+	/// This is written when the paras inherent is entered and cleared on initialization of
+	/// the next block.
+	#[pallet::storage]
+	#[pallet::getter(fn total_backed_candidate_weight)]
+	pub(super) type TotalBackedCandidateWeight<T: Config> = StorageValue<_, Weight, ValueQuery>;
+
+	/// The number of availability cores freed in this block due to disputes.
+	#[pallet::storage]
+	#[pallet::getter(fn freed_by_dispute)]
+	pub(super) type FreedByDispute<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The number of availability cores freed in this block because their candidate became
+	/// available.
+	#[pallet::storage]
+	#[pallet::getter(fn freed_by_concluded)]
+	pub(super) type FreedByConcluded<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The number of availability cores freed in this block due to availability timeouts.
+	#[pallet::storage]
+	#[pallet::getter(fn freed_by_timeout)]
+	pub(super) type FreedByTimeout<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig {
+		/// Whether the paras inherent is considered included in the genesis block, e.g. to
+		/// exercise the `TooManyInclusionInherents` path.
+		pub pre_included: bool,
+	}
+
+	#[cfg(feature = "std")]
+	impl Default for GenesisConfig {
+		fn default() -> Self {
+			GenesisConfig {
+				pre_included: false,
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig {
+		fn build(&self) {
+			if self.pre_included {
+				Included::<T>::set(Some(()));
+			}
+		}
 	}
-}
 
-decl_module! {
-	/// The paras inherent module.
-	pub struct Module<T: Config> for enum Call where origin: <T as frame_system::Config>::Origin {
-		type Error = Error<T>;
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_: T::BlockNumber) -> Weight {
+			TotalBackedCandidateWeight::<T>::kill();
+			FreedByDispute::<T>::kill();
+			FreedByConcluded::<T>::kill();
+			FreedByTimeout::<T>::kill();
+
+			let db_weight = T::DbWeight::get();
+
+			// clearing the per-block statistics above.
+			db_weight.writes(4) +
+				// `Included` in `on_finalize`.
+				db_weight.reads_writes(1, 1) +
+				// `scheduler::clear` in `enter`: the configuration, `Scheduled` and `ParathreadQueue`.
+				db_weight.reads_writes(3, 2) +
+				// `scheduler::schedule` in `enter`: the configuration, `Parachains`, `ValidatorGroups`,
+				// `SessionStartBlock`, `Scheduled`, `ParathreadQueue`, `AvailabilityCores` and
+				// `ParathreadClaimIndex`.
+				db_weight.reads_writes(7, 4)
+		}
+
+		fn on_finalize(_: T::BlockNumber) {
+			if Included::<T>::take().is_none() {
+				T::MissingInherentHandler::handle();
+			}
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			migrations::v1::migrate::<T>()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<(), &'static str> {
+			migrations::v1::pre_migrate::<T>()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			migrations::v1::post_migrate::<T>()
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = MakeFatalError<()>;
+		const INHERENT_IDENTIFIER: InherentIdentifier = PARACHAINS_INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			let mut inherent_data: ParachainsInherentData<T::Header>
+				= match data.get_data(&Self::INHERENT_IDENTIFIER)
+			{
+				Ok(Some(d)) => d,
+				Ok(None) => return None,
+				Err(_) => {
+					log::warn!(
+						target: LOG_TARGET,
+						"ParachainsInherentData failed to decode",
+					);
+
+					return None;
+				}
+			};
+
+			// filter out any unneeded dispute statements
+			T::DisputesHandler::filter_multi_dispute_data(&mut inherent_data.disputes);
+
+			// Sanity check: session changes can invalidate an inherent, and we _really_ don't want that to happen.
+			// See github.com/paritytech/polkadot/issues/1327
+			let inherent_data = match Self::dry_run_enter(inherent_data.clone()) {
+				Ok(_) => inherent_data,
+				Err(err) => {
+					// Candidates which could be invalid are expected while disputes are ongoing,
+					// anything else points to a bug in the node or the runtime.
+					let cause = if err == Error::<T>::CandidateCouldBeInvalid.into() {
+						"disputes"
+					} else {
+						"other"
+					};
+					log::warn!(
+						target: LOG_TARGET,
+						"dropping signed_bitfields and backed_candidates because they produced \
+						an invalid paras inherent: cause={} error={:?}",
+						cause,
+						err,
+					);
+
+					ParachainsInherentData {
+						bitfields: Vec::new(),
+						backed_candidates: Vec::new(),
+						disputes: Vec::new(),
+						parent_header: inherent_data.parent_header,
+					}
+				}
+			};
 
-		fn on_initialize() -> Weight {
-			T::DbWeight::get().reads_writes(1, 1) // in on_finalize.
+			Some(Call::enter(inherent_data))
 		}
 
-		fn on_finalize() {
-			if Included::take().is_none() {
-				panic!("Bitfields and heads must be included every block");
+		fn check_inherent(call: &Self::Call, data: &InherentData) -> Result<(), Self::Error> {
+			let inherent_data = match call {
+				Call::enter(inherent_data) => inherent_data,
+				_ => return Ok(()),
+			};
+
+			// This runs against the state of the parent block, so the active validators of
+			// this block may not be known yet. No session has more than `max_validators` though.
+			if let Some(max_validators) = <configuration::Pallet<T>>::config().max_validators {
+				if inherent_data.bitfields.len() > max_validators as usize {
+					log::debug!(
+						target: LOG_TARGET,
+						"paras inherent has {} signed bitfields, but there are at most {} validators",
+						inherent_data.bitfields.len(),
+						max_validators,
+					);
+
+					return Err(MakeFatalError::from(()));
+				}
+			}
+
+			// The paras inherent data is not necessarily provided when importing blocks, but
+			// if it is, its parent header must be the one of the call.
+			if let Ok(Some(expected)) = data.get_data::<ParachainsInherentData<T::Header>>(
+				&Self::INHERENT_IDENTIFIER,
+			) {
+				if expected.parent_header.hash() != inherent_data.parent_header.hash() {
+					log::debug!(
+						target: LOG_TARGET,
+						"paras inherent parent header hash {:?} does not match the expected {:?}",
+						inherent_data.parent_header.hash(),
+						expected.parent_header.hash(),
+					);
+
+					return Err(MakeFatalError::from(()));
+				}
 			}
+
+			Ok(())
 		}
 
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::enter(..))
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
 		/// Enter the paras inherent. This will process bitfields and backed candidates.
-		#[weight = (
-			MINIMAL_INCLUSION_INHERENT_WEIGHT + data.backed_candidates.len() as Weight * BACKED_CANDIDATE_WEIGHT,
-			DispatchClass::Mandatory,
-		)]
+		#[pallet::weight((claimed_weight::<T>(data), DispatchClass::Mandatory))]
 		pub fn enter(
-			origin,
+			origin: OriginFor<T>,
 			data: ParachainsInherentData<T::Header>,
 		) -> DispatchResultWithPostInfo {
 			let ParachainsInherentData {
-				bitfields: signed_bitfields,
-				backed_candidates,
+				bitfields: mut signed_bitfields,
+				mut backed_candidates,
 				parent_header,
-				disputes,
+				mut disputes,
 			} = data;
 
 			ensure_none(origin)?;
-			ensure!(!<Included>::exists(), Error::<T>::TooManyInclusionInherents);
+			ensure!(!Included::<T>::exists(), Error::<T>::TooManyInclusionInherents);
 
 			// Check that the submitted parent header indeed corresponds to the previous block hash.
 			let parent_hash = <frame_system::Pallet<T>>::parent_hash();
+			let parent_header_hash = parent_header.hash();
+			if parent_header_hash.as_ref() != parent_hash.as_ref() {
+				log::debug!(
+					target: LOG_TARGET,
+					"parent header hash {:?} does not match the expected parent hash {:?}",
+					parent_header_hash,
+					parent_hash,
+				);
+
+				return Err(Error::<T>::InvalidParentHeader.into());
+			}
+
+			// Each active validator signs at most one availability bitfield per block. Check this
+			// before doing any further work on the bitfields.
 			ensure!(
-				parent_header.hash().as_ref() == parent_hash.as_ref(),
-				Error::<T>::InvalidParentHeader,
+				signed_bitfields.len() <= <shared::Pallet<T>>::active_validator_keys().len(),
+				Error::<T>::TooManyBitfields,
 			);
 
+			// Bitfields must be ascending by validator index, so any duplicates of a validator's
+			// bitfield directly follow it. Only keep the first of those. Unordered bitfields are
+			// left as they are, for the inclusion pallet to reject.
+			signed_bitfields.dedup_by(|bitfield, kept| {
+				let duplicate = bitfield.unchecked_validator_index() == kept.unchecked_validator_index();
+				if duplicate {
+					log::warn!(
+						target: LOG_TARGET,
+						"dropping a duplicate bitfield of validator {:?}",
+						bitfield.unchecked_validator_index(),
+					);
+				}
+
+				duplicate
+			});
+			let erasure_chunk_weight = erasure_chunk_weight::<T>(&signed_bitfields);
+
+			// Bound the work done by the disputes handler before handing the statements over.
+			let dispute_statements = disputes.iter()
+				.fold(0usize, |count, set| count.saturating_add(set.statements.len()));
+			ensure!(
+				dispute_statements <= T::MaxDisputeStatementsPerBlock::get() as usize,
+				Error::<T>::TooManyDisputeStatements,
+			);
+
+			// The block author is expected to have filtered the dispute statements already in
+			// `create_inherent`, but the runtime must not rely on that. Filtering notes the spam
+			// slots occupied by the statements, which `provide_multi_dispute_data` does again, so
+			// its changes are discarded.
+			frame_support::storage::with_transaction(|| {
+				T::DisputesHandler::filter_multi_dispute_data(&mut disputes);
+				TransactionOutcome::Rollback(())
+			});
+			let dispute_count = disputes.len() as u32;
+			let dispute_weight = dispute_count as Weight * T::WeightInfo::dispute_weight();
+
 			// Handle disputes logic.
 			let current_session = <shared::Pallet<T>>::session_index();
 			let freed_disputed: Vec<(_, FreedReason)> = {
+				sp_tracing::enter_span!(
+					sp_tracing::Level::DEBUG,
+					"paras_inherent::enter::disputes",
+					disputes = disputes.len()
+				);
+
 				let fresh_disputes = T::DisputesHandler::provide_multi_dispute_data(disputes)?;
 				if T::DisputesHandler::is_frozen() {
 					// The relay chain we are currently on is invalid. Proceed no further on parachains.
-					Included::set(Some(()));
-					return Ok(Some(
-						MINIMAL_INCLUSION_INHERENT_WEIGHT
-					).into());
+					// This is the work measured by the `enter_with_disputes` benchmark.
+					Included::<T>::set(Some(()));
+					LastIncludedBlockNumber::<T>::put(<frame_system::Pallet<T>>::block_number());
+					return Ok(Some(T::WeightInfo::enter_with_disputes(dispute_count)).into());
 				}
 
 				let any_current_session_disputes = fresh_disputes.iter()
@@ -137,8 +628,19 @@ decl_module! {
 						.map(|(_, c)| *c)
 						.collect();
 
-					<inclusion::Pallet<T>>::collect_disputed(current_session_disputes)
-						.into_iter()
+					let freed_cores =
+						<inclusion::Pallet<T>>::collect_disputed(current_session_disputes.clone());
+
+					if !freed_cores.is_empty() {
+						log::debug!(
+							target: LOG_TARGET,
+							"freed cores {:?} due to disputes of the candidates {:?}",
+							freed_cores,
+							current_session_disputes,
+						);
+					}
+
+					freed_cores.into_iter()
 						.map(|core| (core, FreedReason::Concluded))
 						.collect()
 				} else {
@@ -146,14 +648,28 @@ decl_module! {
 				}
 			};
 
+			// The paras whose candidates pending availability were just disputed. Their cores are
+			// still marked as occupied until the scheduler is updated below.
+			let disputed_paras = freed_disputed.iter()
+				.filter_map(|(core, _)| <scheduler::Module<T>>::core_para(*core))
+				.collect::<BTreeSet<_>>();
+
 			// Process new availability bitfields, yielding any availability cores whose
 			// work has now concluded.
 			let expected_bits = <scheduler::Module<T>>::availability_cores().len();
-			let freed_concluded = <inclusion::Pallet<T>>::process_bitfields(
-				expected_bits,
-				signed_bitfields,
-				<scheduler::Module<T>>::core_para,
-			)?;
+			let freed_concluded = {
+				sp_tracing::enter_span!(
+					sp_tracing::Level::DEBUG,
+					"paras_inherent::enter::bitfields",
+					bitfields = signed_bitfields.len()
+				);
+
+				<inclusion::Pallet<T>>::process_bitfields(
+					expected_bits,
+					signed_bitfields,
+					<scheduler::Module<T>>::core_para,
+				)?
+			};
 
 			// Inform the disputes module of all included candidates.
 			let now = <frame_system::Pallet<T>>::block_number();
@@ -169,83 +685,391 @@ decl_module! {
 				Vec::new()
 			};
 
+			FreedByDispute::<T>::put(freed_disputed.len() as u32);
+			FreedByConcluded::<T>::put(freed_concluded.len() as u32);
+			FreedByTimeout::<T>::put(freed_timeout.len() as u32);
+
 			// Schedule paras again, given freed cores, and reasons for freeing.
-			let mut freed = freed_disputed.into_iter()
-				.chain(freed_concluded.into_iter().map(|(c, _hash)| (c, FreedReason::Concluded)))
-				.chain(freed_timeout.into_iter().map(|c| (c, FreedReason::TimedOut)))
-				.collect::<Vec<_>>();
+			{
+				sp_tracing::enter_span!(
+					sp_tracing::Level::DEBUG,
+					"paras_inherent::enter::scheduling",
+					freed_disputed = freed_disputed.len(),
+					freed_concluded = freed_concluded.len(),
+					freed_timeout = freed_timeout.len()
+				);
 
-			freed.sort_unstable_by_key(|pair| pair.0); // sort by core index
+				let mut freed = freed_disputed.into_iter()
+					.chain(freed_concluded.into_iter().map(|(c, _hash)| (c, FreedReason::Concluded)))
+					.chain(freed_timeout.into_iter().map(|c| (c, FreedReason::TimedOut)))
+					.collect::<Vec<_>>();
 
-			<scheduler::Module<T>>::clear();
-			<scheduler::Module<T>>::schedule(
-				freed,
-				<frame_system::Pallet<T>>::block_number(),
-			);
+				sort_freed_cores(&mut freed);
+
+				<scheduler::Module<T>>::clear();
+				<scheduler::Module<T>>::schedule(
+					freed,
+					<frame_system::Pallet<T>>::block_number(),
+				);
+			}
+
+			// Drop the candidates rejected by the runtime's policy, as if they were never proposed.
+			backed_candidates.retain(|candidate| {
+				let accepted = T::CandidateFilter::filter(candidate);
+				if !accepted {
+					log::debug!(
+						target: LOG_TARGET,
+						"dropping candidate {:?} of para {:?} rejected by the candidate filter",
+						candidate.hash(),
+						candidate.descriptor().para_id,
+					);
+				}
 
-			let backed_candidates = limit_backed_candidates::<T>(backed_candidates);
+				accepted
+			});
+
+			let proposed_candidates = backed_candidates.len() as u32;
+			let (backed_candidates, truncation_reason) =
+				limit_backed_candidates::<T>(backed_candidates, &disputed_paras);
 			let backed_candidates_len = backed_candidates.len() as Weight;
 
 			// Refuse to back any candidates that are disputed or invalid.
-			for candidate in &backed_candidates {
-				ensure!(
-					!T::DisputesHandler::could_be_invalid(
-						current_session,
-						candidate.candidate.hash(),
-					),
-					Error::<T>::CandidateCouldBeInvalid,
-				);
-			}
+			let candidate_hashes = backed_candidates.iter()
+				.map(|candidate| candidate.hash())
+				.collect::<Vec<_>>();
+			ensure!(
+				!T::DisputesHandler::any_could_be_invalid(current_session, &candidate_hashes),
+				Error::<T>::CandidateCouldBeInvalid,
+			);
 
 			// Process backed candidates according to scheduled cores.
 			let parent_storage_root = parent_header.state_root().clone();
-			let occupied = <inclusion::Pallet<T>>::process_candidates(
-				parent_storage_root,
-				backed_candidates,
-				<scheduler::Module<T>>::scheduled(),
-				<scheduler::Module<T>>::group_validators,
-			)?;
+			let occupied = {
+				sp_tracing::enter_span!(
+					sp_tracing::Level::DEBUG,
+					"paras_inherent::enter::backing",
+					candidates = backed_candidates.len()
+				);
+
+				<inclusion::Pallet<T>>::process_candidates(
+					parent_storage_root,
+					backed_candidates,
+					<scheduler::Module<T>>::scheduled(),
+					<scheduler::Module<T>>::group_validators,
+				)?
+			};
 
 			// Note which of the scheduled cores were actually occupied by a backed candidate.
 			<scheduler::Module<T>>::occupied(&occupied);
 
 			// Give some time slice to dispatch pending upward messages.
-			<ump::Pallet<T>>::process_pending_upward_messages();
+			let (upward_message_count, upward_message_weight) = {
+				sp_tracing::enter_span!(sp_tracing::Level::DEBUG, "paras_inherent::enter::ump");
+
+				<ump::Pallet<T>>::process_pending_upward_messages()
+			};
+			if upward_message_count > 0 {
+				Self::deposit_event(Event::UpwardMessagesProcessed(
+					upward_message_count,
+					upward_message_weight,
+				));
+			}
+
+			let backed_candidate_weight =
+				backed_candidates_len * T::WeightInfo::backed_candidate_weight();
+			TotalBackedCandidateWeight::<T>::put(backed_candidate_weight);
+
+			LastBackedCandidateHashes::<T>::put(candidate_hashes);
+
+			let digest = <frame_system::Pallet<T>>::digest();
+			let pre_runtime_digests = digest.logs().iter().filter_map(|d| d.as_pre_runtime());
+			if let Some(author) = T::FindAuthor::find_author(pre_runtime_digests) {
+				T::BackingRewardHandler::reward_backed(author, backed_candidates_len as u32);
+			}
+
+			let outcome = match truncation_reason {
+				None => InherentOutcome::Normal { backed_count: proposed_candidates },
+				Some(reason) => InherentOutcome::Truncated {
+					proposed: proposed_candidates,
+					accepted: backed_candidates_len as u32,
+					reason,
+				},
+			};
+			Self::deposit_event(Event::InherentProcessed(outcome));
 
 			// And track that we've finished processing the inherent for this block.
-			Included::set(Some(()));
+			Included::<T>::set(Some(()));
+			LastIncludedBlockNumber::<T>::put(now);
+
+			if truncation_reason.is_some() &&
+				T::WeightRefundPolicy::get() == WeightRefundPolicy::NeverRefundOnTruncation
+			{
+				// charge the full weight claimed for all proposed candidates.
+				return Ok(None::<Weight>.into());
+			}
 
 			Ok(Some(
-				MINIMAL_INCLUSION_INHERENT_WEIGHT +
-				(backed_candidates_len * BACKED_CANDIDATE_WEIGHT)
+				T::WeightInfo::base_weight() +
+					backed_candidate_weight +
+					erasure_chunk_weight +
+					dispute_weight +
+					upward_message_weight
 			).into())
 		}
+
+		/// Enter the paras inherent from a signed origin, for test networks without a block
+		/// author providing the inherent.
+		///
+		/// This fails with `TestModeDisabled` unless `T::TestMode` is enabled, charging the full
+		/// declared weight. Otherwise, the data is processed exactly as by `enter`, which still
+		/// fails if the inherent was already included.
+		#[pallet::weight(claimed_weight::<T>(data))]
+		pub fn try_enter(
+			origin: OriginFor<T>,
+			data: ParachainsInherentData<T::Header>,
+		) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			ensure!(T::TestMode::get(), Error::<T>::TestModeDisabled);
+
+			Self::enter(frame_system::RawOrigin::None.into(), data)
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Simulate entering the paras inherent with the given data, without persisting any of the
+	/// resulting storage changes.
+	///
+	/// Returns the weight the inherent would consume, or the error it would fail with.
+	pub fn dry_run_enter(data: ParachainsInherentData<T::Header>) -> Result<Weight, DispatchError> {
+		let claimed_weight = claimed_weight::<T>(&data);
+
+		frame_support::storage::with_transaction(|| {
+			let result = Self::enter(frame_system::RawOrigin::None.into(), data)
+				.map(|post_info| post_info.actual_weight.unwrap_or(claimed_weight))
+				.map_err(|e| e.error);
+
+			TransactionOutcome::Rollback(result)
+		})
+	}
+
+	/// The number of candidates which are currently pending availability.
+	pub fn pending_availability_count() -> u32 {
+		<inclusion::Pallet<T>>::pending_availability_count()
+	}
+
+	/// The indices of the active validators which did not submit a bitfield in this block.
+	pub fn pending_bitfields() -> Vec<primitives::v1::ValidatorIndex> {
+		<inclusion::Pallet<T>>::pending_bitfields()
+	}
+
+	/// The number of availability cores which are currently scheduled.
+	pub fn scheduled_cores_count() -> u32 {
+		<scheduler::Module<T>>::scheduled().len() as u32
+	}
+
+	/// The para of the candidate pending availability on each availability core, or `None` if
+	/// the core is free.
+	pub fn availability_core_occupancy() -> Vec<Option<ParaId>> {
+		let mut occupancy = vec![None; <scheduler::Module<T>>::availability_cores().len()];
+		for (core, para_id) in <inclusion::Pallet<T>>::occupied_cores() {
+			if let Some(slot) = occupancy.get_mut(core.0 as usize) {
+				*slot = Some(para_id);
+			}
+		}
+
+		occupancy
+	}
+}
+
+/// Storage migrations of the paras inherent.
+pub mod migrations {
+	use super::*;
+	use frame_support::traits::{GetStorageVersion, StorageVersion};
+
+	/// Migration to storage version 1.
+	pub mod v1 {
+		use super::*;
+
+		/// The storage prefix of version 0, under which `decl_storage` placed `Included`.
+		///
+		/// Version 1 storage is prefixed by the name of the pallet in `construct_runtime!`,
+		/// which is `ParasInherent` in the Kusama, Rococo and Westend runtimes.
+		const V0_PREFIX: &[u8] = b"ParaInherent";
+
+		/// Migrate the storage from version 0, which only contained `Included`, to version 1,
+		/// which adds the backed candidate weight and the freed core counters.
+		///
+		/// The new items are cleared at the start of every block, so they are initialized to
+		/// their defaults. `Included` is reset to `None`, as it must never outlive a block, and
+		/// its version 0 key is removed.
+		///
+		/// Does nothing if the storage is already at version 1 or later.
+		pub fn migrate<T: Config>() -> Weight {
+			let db_weight = T::DbWeight::get();
+
+			let on_chain_version = Pallet::<T>::on_chain_storage_version();
+			if on_chain_version >= 1 {
+				log::info!(
+					target: LOG_TARGET,
+					"paras inherent storage is at version {:?}, skipping the migration to v1",
+					on_chain_version,
+				);
+
+				return db_weight.reads(1);
+			}
+
+			frame_support::migration::remove_storage_prefix(V0_PREFIX, b"Included", b"");
+			Included::<T>::kill();
+			TotalBackedCandidateWeight::<T>::kill();
+			FreedByDispute::<T>::kill();
+			FreedByConcluded::<T>::kill();
+			FreedByTimeout::<T>::kill();
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			log::info!(target: LOG_TARGET, "migrated paras inherent storage to v1");
+
+			// the storage version, and writing the removed version 0 key, the cleared items as
+			// well as the new version.
+			db_weight.reads_writes(1, 7)
+		}
+
+		/// Check that the migration to version 1 is pending.
+		#[cfg(feature = "try-runtime")]
+		pub fn pre_migrate<T: Config>() -> Result<(), &'static str> {
+			frame_support::ensure!(
+				Pallet::<T>::on_chain_storage_version() < 1,
+				"paras inherent storage is already at version 1 or later",
+			);
+
+			Ok(())
+		}
+
+		/// Check that the storage is at version 1, the new items are initialized and the
+		/// version 0 `Included` is removed.
+		#[cfg(feature = "try-runtime")]
+		pub fn post_migrate<T: Config>() -> Result<(), &'static str> {
+			frame_support::ensure!(
+				Pallet::<T>::on_chain_storage_version() == 1,
+				"paras inherent storage was not migrated to version 1",
+			);
+			frame_support::ensure!(!Included::<T>::exists(), "`Included` was not reset");
+			frame_support::ensure!(
+				frame_support::migration::get_storage_value::<()>(V0_PREFIX, b"Included", b"")
+					.is_none(),
+				"the version 0 `Included` was not removed",
+			);
+			frame_support::ensure!(
+				TotalBackedCandidateWeight::<T>::get() == 0 &&
+					FreedByDispute::<T>::get() == 0 &&
+					FreedByConcluded::<T>::get() == 0 &&
+					FreedByTimeout::<T>::get() == 0,
+				"the per-block statistics were not initialized",
+			);
+
+			Ok(())
+		}
 	}
 }
 
+/// Sort freed cores by core index, ordering entries for the same core by the reason for freeing.
+///
+/// A core is expected to be freed at most once per block, but sorting on both keys keeps the
+/// result deterministic even if that assumption is broken.
+fn sort_freed_cores(freed: &mut [(CoreIndex, FreedReason)]) {
+	freed.sort_unstable_by_key(|pair| (pair.0, pair.1 as u8));
+}
+
+/// The weight claimed by the paras inherent for the given data, before any of it is dropped.
+///
+/// This includes the full budget for dispatching upward messages, as the weight they actually
+/// consume is only known after dispatch, and the weight charged never exceeds the claimed one.
+fn claimed_weight<T: Config>(data: &ParachainsInherentData<T::Header>) -> Weight {
+	T::WeightInfo::base_weight() +
+		data.backed_candidates.len() as Weight * T::WeightInfo::backed_candidate_weight() +
+		erasure_chunk_weight::<T>(&data.bitfields) +
+		data.disputes.len() as Weight * T::WeightInfo::dispute_weight() +
+		<configuration::Pallet<T>>::config().ump_service_total_weight
+}
+
+/// The weight of noting the availability of all erasure chunks the given bitfields claim.
+///
+/// Each set bit of a bitfield claims the erasure chunk of the candidate pending availability
+/// on the corresponding core. The bitfields are not validated yet, so only the bits of existing
+/// availability cores are counted. Bitfields of any other size are rejected by the inclusion
+/// pallet anyway.
+fn erasure_chunk_weight<T: scheduler::Config>(
+	bitfields: &[UncheckedSignedAvailabilityBitfield],
+) -> Weight {
+	let n_cores = <scheduler::Module<T>>::availability_cores().len();
+	let chunks: usize = bitfields.iter()
+		.map(|bitfield| {
+			let bits = &bitfield.unchecked_payload().0;
+			bits[..bits.len().min(n_cores)].count_ones()
+		})
+		.sum();
+
+	chunks as Weight * ERASURE_CHUNK_WEIGHT
+}
+
 /// Limit the number of backed candidates processed in order to stay within block weight limits.
 ///
 /// Use a configured assumption about the weight required to process a backed candidate and the
 /// current block weight as of the execution of this function to ensure that we don't overload
 /// the block with candidate processing.
 ///
-/// If the backed candidates exceed the available block weight remaining, then skips all of them.
-/// This is somewhat less desirable than attempting to fit some of them, but is more fair in the
-/// even that we can't trust the provisioner to provide a fair / random ordering of candidates.
+/// At most `T::MaxBackedCandidatesPerPara` candidates of each para are kept, depositing an
+/// `ExcessCandidatesDropped` event for each para which had candidates dropped.
+///
+/// If the backed candidates exceed the available block weight remaining, then the list is truncated
+/// to the number of candidates which still fit into the block. Candidates of `disputed_paras` are
+/// dropped before any others. Beyond that, the provisioner is trusted to provide a fair ordering of
+/// candidates, as the candidates at the end of the list are dropped first. The remaining candidates
+/// keep their relative order, as they must be processed in the order of their cores.
+///
+/// Returns the remaining candidates, along with the reason for the last truncation applied, if any.
 fn limit_backed_candidates<T: Config>(
 	mut backed_candidates: Vec<BackedCandidate<T::Hash>>,
-) -> Vec<BackedCandidate<T::Hash>> {
-	const MAX_CODE_UPGRADES: usize = 1;
+	disputed_paras: &BTreeSet<ParaId>,
+) -> (Vec<BackedCandidate<T::Hash>>, Option<TruncationReason>) {
+	let mut truncation_reason = None;
+	let max_code_upgrades = T::MaxCodeUpgradesPerInherent::get();
+	let max_per_para = T::MaxBackedCandidatesPerPara::get();
+
+	// Ignore any candidates of a para beyond the configured maximum per para, so that a single
+	// para cannot crowd out all others.
+	{
+		let mut backed_per_para = BTreeMap::<ParaId, u32>::new();
+		let mut dropped_per_para = BTreeMap::<ParaId, u32>::new();
+		backed_candidates.retain(|c| {
+			let para_id = c.descriptor().para_id;
+			let backed = backed_per_para.entry(para_id).or_insert(0);
+			if *backed >= max_per_para {
+				*dropped_per_para.entry(para_id).or_insert(0) += 1;
+				return false
+			}
+
+			*backed += 1;
+			true
+		});
+
+		for (para_id, dropped) in dropped_per_para {
+			<Pallet<T>>::deposit_event(Event::ExcessCandidatesDropped(para_id, dropped));
+			truncation_reason = Some(TruncationReason::PerParaLimit);
+		}
+	}
 
-	// Ignore any candidates beyond one that contain code upgrades.
+	// Ignore any candidates beyond the configured maximum that contain code upgrades.
 	//
 	// This is an artificial limitation that does not appear in the guide as it is a practical
 	// concern around execution.
 	{
+		let proposed = backed_candidates.len();
 		let mut code_upgrades = 0;
 		backed_candidates.retain(|c| {
 			if c.candidate.commitments.new_validation_code.is_some() {
-				if code_upgrades >= MAX_CODE_UPGRADES {
+				if code_upgrades >= max_code_upgrades {
 					return false
 				}
 
@@ -254,71 +1078,84 @@ fn limit_backed_candidates<T: Config>(
 
 			true
 		});
-	}
 
-	// the weight of the paras inherent is already included in the current block weight,
-	// so our operation is simple: if the block is currently overloaded, make this intrinsic smaller
-	if frame_system::Pallet::<T>::block_weight().total() > <T as frame_system::Config>::BlockWeights::get().max_block {
-		Vec::new()
-	} else {
-		backed_candidates
+		if backed_candidates.len() != proposed {
+			truncation_reason = Some(TruncationReason::CodeUpgrades);
+		}
 	}
-}
 
-impl<T: Config> ProvideInherent for Module<T> {
-	type Call = Call<T>;
-	type Error = MakeFatalError<()>;
-	const INHERENT_IDENTIFIER: InherentIdentifier = PARACHAINS_INHERENT_IDENTIFIER;
-
-	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
-		let mut inherent_data: ParachainsInherentData<T::Header>
-			= match data.get_data(&Self::INHERENT_IDENTIFIER)
-		{
-			Ok(Some(d)) => d,
-			Ok(None) => return None,
-			Err(_) => {
-				log::warn!(
-					target: LOG_TARGET,
-					"ParachainsInherentData failed to decode",
-				);
+	// the weight of the paras inherent is already included in the current block weight,
+	// so our operation is simple: if the block is currently overloaded, drop as many candidates
+	// as needed to make up for the excess weight.
+	let block_weight = frame_system::Pallet::<T>::block_weight().total();
+	let max_block_weight = <T as frame_system::Config>::BlockWeights::get().max_block;
+	if block_weight > max_block_weight {
+		let candidate_weight = T::WeightInfo::backed_candidate_weight().max(1);
+		let excess_weight = block_weight - max_block_weight;
+		let excess_candidates = (excess_weight + candidate_weight - 1) / candidate_weight;
+		let remaining = (backed_candidates.len() as Weight).saturating_sub(excess_candidates);
+		if (remaining as usize) < backed_candidates.len() {
+			// a stable sort, so undisputed candidates come first and both groups keep their order.
+			let mut by_priority = (0..backed_candidates.len()).collect::<Vec<_>>();
+			by_priority.sort_by_key(|&i| {
+				disputed_paras.contains(&backed_candidates[i].descriptor().para_id)
+			});
 
-				return None;
+			let mut keep = vec![false; backed_candidates.len()];
+			for &i in by_priority.iter().take(remaining as usize) {
+				keep[i] = true;
 			}
-		};
 
-		// filter out any unneeded dispute statements
-		T::DisputesHandler::filter_multi_dispute_data(&mut inherent_data.disputes);
-
-		// Sanity check: session changes can invalidate an inherent, and we _really_ don't want that to happen.
-		// See github.com/paritytech/polkadot/issues/1327
-		let inherent_data = match Self::enter(
-			frame_system::RawOrigin::None.into(),
-			inherent_data.clone(),
-		) {
-			Ok(_) => inherent_data,
-			Err(err) => {
-				log::warn!(
-					target: LOG_TARGET,
-					"dropping signed_bitfields and backed_candidates because they produced \
-					an invalid paras inherent: {:?}",
-					err,
-				);
+			let mut keep = keep.into_iter();
+			backed_candidates.retain(|_| keep.next().unwrap_or(false));
+			truncation_reason = Some(TruncationReason::BlockWeight);
+		}
+	}
 
-				ParachainsInherentData {
-					bitfields: Vec::new(),
-					backed_candidates: Vec::new(),
-					disputes: Vec::new(),
-					parent_header: inherent_data.parent_header,
-				}
-			}
-		};
+	(backed_candidates, truncation_reason)
+}
 
-		Some(Call::enter(inherent_data))
-	}
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking {
+	use super::*;
+	use frame_system::RawOrigin;
+	use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+
+	benchmarks! {
+		where_clause { where T: BenchmarkHelper }
+
+		enter_backed_candidates {
+			let c in 0 .. T::MAX_BACKED_CANDIDATES;
+			let data = T::inherent_data(c);
+		}: enter(RawOrigin::None, data)
+		verify {
+			assert_eq!(LastBackedCandidateHashes::<T>::get().len(), c as usize);
+		}
 
-	fn is_inherent(call: &Self::Call) -> bool {
-		matches!(call, Call::enter(..))
+		enter_with_disputes {
+			let d in 0 .. 100;
+			let mut data = T::inherent_data(0);
+			let session = <shared::Pallet<T>>::session_index();
+			data.disputes = (0..d).map(|i| T::fresh_dispute(i, session)).collect();
+			let candidate_hashes = data.disputes.iter()
+				.map(|set| set.candidate_hash)
+				.collect::<Vec<_>>();
+		}: enter(RawOrigin::None, data)
+		verify {
+			// none of the disputes were filtered out, and all of them are ongoing. Runtimes which
+			// don't handle disputes, i.e. use the `()` handler, ignore all of them.
+			let ongoing = candidate_hashes.iter()
+				.filter(|candidate_hash| T::DisputesHandler::could_be_invalid(session, **candidate_hash))
+				.count();
+			assert!(ongoing == 0 || ongoing == candidate_hashes.len());
+		}
 	}
+
+	impl_benchmark_test_suite!(
+		Pallet,
+		crate::mock::new_test_ext(Default::default()),
+		crate::mock::Test,
+	);
 }
 
 #[cfg(test)]
@@ -326,18 +1163,52 @@ mod tests {
 	use super::*;
 
 	use crate::mock::{
-		new_test_ext, System, MockGenesisConfig, Test
+		new_test_ext, System, MockGenesisConfig, MaxBackedCandidatesPerPara,
+		MaxCodeUpgradesPerInherent, MaxDisputeStatementsPerBlock, ParasInherentTestMode,
+		RejectedCandidatePara, Test,
 	};
+	use crate::test_helpers::{start_block, ParachainInherentDataBuilder};
+	use primitives::v1::Header;
+
+	fn base_weight() -> Weight {
+		<Test as Config>::WeightInfo::base_weight()
+	}
+
+	fn backed_candidate_weight() -> Weight {
+		<Test as Config>::WeightInfo::backed_candidate_weight()
+	}
+
+	fn dispute_weight() -> Weight {
+		<Test as Config>::WeightInfo::dispute_weight()
+	}
+
+	fn default_header() -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number: 0,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	/// Start block 1 and get inherent data for it without any bitfields, backed candidates or
+	/// disputes.
+	fn new_block_data() -> ParachainsInherentData<Header> {
+		ParachainInherentDataBuilder::<Test>::new().build(start_block::<Test>())
+	}
 
 	mod limit_backed_candidates {
 		use super::*;
 
+		use crate::mock::Event as MockEvent;
+
 		#[test]
 		fn does_not_truncate_on_empty_block() {
 			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
 				let backed_candidates = vec![BackedCandidate::default()];
 				System::set_block_consumed_resources(0, 0);
-				assert_eq!(limit_backed_candidates::<Test>(backed_candidates).len(), 1);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 1);
 			});
 		}
 
@@ -348,7 +1219,7 @@ mod tests {
 				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
 				// if the consumed resources are precisely equal to the max block weight, we do not truncate.
 				System::set_block_consumed_resources(max_block_weight, 0);
-				assert_eq!(limit_backed_candidates::<Test>(backed_candidates).len(), 1);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 1);
 			});
 		}
 
@@ -359,7 +1230,7 @@ mod tests {
 				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
 				// if the consumed resources are precisely equal to the max block weight, we do not truncate.
 				System::set_block_consumed_resources(max_block_weight + 1, 0);
-				assert_eq!(limit_backed_candidates::<Test>(backed_candidates).len(), 0);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 0);
 			});
 		}
 
@@ -368,9 +1239,72 @@ mod tests {
 			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
 				let backed_candidates = vec![BackedCandidate::default(); 10];
 				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
-				// if the consumed resources are precisely equal to the max block weight, we do not truncate.
-				System::set_block_consumed_resources(max_block_weight + 1, 0);
-				assert_eq!(limit_backed_candidates::<Test>(backed_candidates).len(), 0);
+				// if the excess weight covers all candidates, all of them are truncated.
+				System::set_block_consumed_resources(max_block_weight + 10 * backed_candidate_weight(), 0);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 0);
+			});
+		}
+
+		#[test]
+		fn some_backed_candidates_get_truncated() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let backed_candidates: Vec<_> = (0..10u32)
+					.map(|i| {
+						let mut backed = BackedCandidate::default();
+						backed.candidate.descriptor.para_id = i.into();
+						backed
+					})
+					.collect();
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				// the block is over-full by the weight of 7 candidates, so only the first 3 fit.
+				System::set_block_consumed_resources(max_block_weight + 7 * backed_candidate_weight(), 0);
+				assert_eq!(
+					limit_backed_candidates::<Test>(backed_candidates.clone(), &Default::default()),
+					(backed_candidates[..3].to_vec(), Some(TruncationReason::BlockWeight)),
+				);
+			});
+		}
+
+		#[test]
+		fn partial_excess_weight_truncates_a_whole_candidate() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let backed_candidates = vec![BackedCandidate::default(); 10];
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				// an excess of slightly more than one candidate's weight drops two candidates.
+				System::set_block_consumed_resources(max_block_weight + backed_candidate_weight() + 1, 0);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 8);
+			});
+		}
+
+		#[test]
+		fn drops_candidates_of_disputed_paras_first() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let backed_candidates: Vec<_> = (0..4u32)
+					.map(|i| {
+						let mut backed = BackedCandidate::default();
+						backed.candidate.descriptor.para_id = i.into();
+						backed
+					})
+					.collect();
+				let disputed_paras = vec![ParaId::from(1), ParaId::from(2)].into_iter().collect();
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+
+				let paras_after_dropping = |excess_candidates: Weight| {
+					System::set_block_consumed_resources(
+						max_block_weight + excess_candidates * backed_candidate_weight(),
+						0,
+					);
+
+					limit_backed_candidates::<Test>(backed_candidates.clone(), &disputed_paras).0
+						.into_iter()
+						.map(|c| c.descriptor().para_id)
+						.collect::<Vec<_>>()
+				};
+
+				// the remaining candidates keep their original order.
+				assert_eq!(paras_after_dropping(1), vec![0.into(), 1.into(), 3.into()]);
+				assert_eq!(paras_after_dropping(2), vec![0.into(), 3.into()]);
+				assert_eq!(paras_after_dropping(3), vec![0.into()]);
 			});
 		}
 
@@ -380,40 +1314,793 @@ mod tests {
 				let mut backed = BackedCandidate::default();
 				backed.candidate.commitments.new_validation_code = Some(Vec::new().into());
 				let backed_candidates = (0..3).map(|_| backed.clone()).collect();
-				assert_eq!(limit_backed_candidates::<Test>(backed_candidates).len(), 1);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 1);
+			});
+		}
+
+		#[test]
+		fn reports_truncation_reason() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let mut backed = BackedCandidate::default();
+				backed.candidate.commitments.new_validation_code = Some(Vec::new().into());
+
+				let backed_candidates = vec![BackedCandidate::default(); 2];
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).1, None);
+
+				let backed_candidates = vec![backed.clone(), backed];
+				assert_eq!(
+					limit_backed_candidates::<Test>(backed_candidates.clone(), &Default::default()).1,
+					Some(TruncationReason::CodeUpgrades),
+				);
+
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				System::set_block_consumed_resources(max_block_weight + 1, 0);
+				assert_eq!(
+					limit_backed_candidates::<Test>(backed_candidates, &Default::default()).1,
+					Some(TruncationReason::BlockWeight),
+				);
+			});
+		}
+
+		#[test]
+		fn respects_configured_max_code_upgrades() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let mut backed = BackedCandidate::default();
+				backed.candidate.commitments.new_validation_code = Some(Vec::new().into());
+				let backed_candidates: Vec<_> = (0..5).map(|_| backed.clone()).collect();
+
+				MaxCodeUpgradesPerInherent::set(3);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates.clone(), &Default::default()).0.len(), 3);
+
+				MaxCodeUpgradesPerInherent::set(0);
+				assert_eq!(limit_backed_candidates::<Test>(backed_candidates, &Default::default()).0.len(), 0);
+
+				MaxCodeUpgradesPerInherent::set(1);
+			});
+		}
+
+		#[test]
+		fn respects_configured_max_backed_candidates_per_para() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				System::set_block_number(1);
+
+				let backed_candidates: Vec<_> = vec![0u32, 1, 0, 0, 2, 1]
+					.into_iter()
+					.map(|i| {
+						let mut backed = BackedCandidate::default();
+						backed.candidate.descriptor.para_id = i.into();
+						backed
+					})
+					.collect();
+
+				MaxBackedCandidatesPerPara::set(1);
+				let (remaining, reason) =
+					limit_backed_candidates::<Test>(backed_candidates, &Default::default());
+				MaxBackedCandidatesPerPara::set(u32::MAX);
+
+				assert_eq!(
+					remaining.iter().map(|c| c.descriptor().para_id).collect::<Vec<_>>(),
+					vec![0.into(), 1.into(), 2.into()],
+				);
+				assert_eq!(reason, Some(TruncationReason::PerParaLimit));
+
+				let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+				assert_eq!(
+					events,
+					vec![
+						MockEvent::ParaInherent(Event::ExcessCandidatesDropped(0.into(), 2)),
+						MockEvent::ParaInherent(Event::ExcessCandidatesDropped(1.into(), 1)),
+					],
+				);
 			});
 		}
+
+		mod properties {
+			use super::*;
+
+			use proptest::prelude::*;
+
+			fn backed_candidates(code_upgrades: &[bool]) -> Vec<BackedCandidate<primitives::v1::Hash>> {
+				code_upgrades.iter()
+					.map(|&code_upgrade| {
+						let mut backed = BackedCandidate::default();
+						if code_upgrade {
+							backed.candidate.commitments.new_validation_code = Some(Vec::new().into());
+						}
+						backed
+					})
+					.collect()
+			}
+
+			proptest! {
+				#![proptest_config(ProptestConfig::with_cases(128))]
+
+				#[test]
+				fn respects_all_limits(
+					code_upgrades in proptest::collection::vec(any::<bool>(), 0..20),
+					max_code_upgrades in 0u32..4,
+					// in units of a hundredth of a candidate's weight, up to 25 candidates over the limit.
+					excess_weight in -1_000i64..2_500,
+				) {
+					new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+						let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+						let excess_weight = excess_weight * backed_candidate_weight() as i64 / 100;
+						System::set_block_consumed_resources((max_block_weight as i64 + excess_weight) as Weight, 0);
+
+						MaxCodeUpgradesPerInherent::set(max_code_upgrades);
+						let (limited, _) = limit_backed_candidates::<Test>(
+							backed_candidates(&code_upgrades),
+							&Default::default(),
+						);
+						MaxCodeUpgradesPerInherent::set(1);
+
+						assert!(limited.len() <= code_upgrades.len());
+
+						let limited_code_upgrades = limited.iter()
+							.filter(|c| c.candidate.commitments.new_validation_code.is_some())
+							.count();
+						assert!(limited_code_upgrades <= max_code_upgrades as usize);
+
+						if excess_weight > 0 {
+							// enough candidates are dropped to make up for the excess weight.
+							let excess_candidates = (excess_weight as Weight + backed_candidate_weight() - 1) /
+								backed_candidate_weight();
+							assert!(limited.len() as Weight <= (code_upgrades.len() as Weight).saturating_sub(excess_candidates));
+						} else {
+							// only excess code upgrades are dropped.
+							let proposed_code_upgrades = code_upgrades.iter().filter(|&&c| c).count();
+							assert_eq!(
+								limited.len(),
+								code_upgrades.len() - proposed_code_upgrades.saturating_sub(max_code_upgrades as usize),
+							);
+						}
+					});
+				}
+			}
+		}
 	}
 
-	mod paras_inherent_weight {
+	mod sort_freed_cores {
 		use super::*;
 
-		use crate::mock::{
-			new_test_ext, System, MockGenesisConfig, Test
+		#[test]
+		fn orders_by_core_then_reason() {
+			let mut freed = vec![
+				(CoreIndex(2), FreedReason::TimedOut),
+				(CoreIndex(1), FreedReason::TimedOut),
+				(CoreIndex(2), FreedReason::Concluded),
+				(CoreIndex(0), FreedReason::Concluded),
+				(CoreIndex(1), FreedReason::Concluded),
+			];
+
+			sort_freed_cores(&mut freed);
+
+			assert_eq!(
+				freed,
+				vec![
+					(CoreIndex(0), FreedReason::Concluded),
+					(CoreIndex(1), FreedReason::Concluded),
+					(CoreIndex(1), FreedReason::TimedOut),
+					(CoreIndex(2), FreedReason::Concluded),
+					(CoreIndex(2), FreedReason::TimedOut),
+				],
+			);
+		}
+	}
+
+	mod enter {
+		use super::*;
+
+		use frame_support::traits::UnfilteredDispatchable;
+		use keyring::Sr25519Keyring;
+		use bitvec::order::Lsb0 as BitOrderLsb0;
+		use primitives::v1::{
+			AvailabilityBitfield, DisputeStatement, DisputeStatementSet, ExplicitDisputeStatement,
+			UncheckedSigned, UncheckedSignedAvailabilityBitfield, ValidDisputeStatementKind,
+			ValidatorId, ValidatorIndex,
 		};
-		use primitives::v1::Header;
+		use crate::mock::DisputeDataError;
+
+		fn unchecked_bitfield(validator_index: u32) -> UncheckedSignedAvailabilityBitfield {
+			UncheckedSigned::new(
+				AvailabilityBitfield(Default::default()),
+				ValidatorIndex(validator_index),
+				sp_core::sr25519::Signature([0u8; 64]).into(),
+			)
+		}
+
+		#[test]
+		fn rejects_more_bitfields_than_validators() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				<shared::Pallet<Test>>::set_active_validators_ascending(
+					vec![Sr25519Keyring::Alice.public().into()],
+				);
+
+				let err = Call::<Test>::enter(ParachainsInherentData {
+					bitfields: vec![unchecked_bitfield(0), unchecked_bitfield(1)],
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap_err();
+
+				assert_eq!(err.error, Error::<Test>::TooManyBitfields.into());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		/// Genesis with a single registered parachain, whose candidates don't time out while
+		/// pending availability in the first few blocks.
+		fn genesis_with_para(chain: ParaId) -> MockGenesisConfig {
+			let mut genesis = MockGenesisConfig {
+				paras: crate::paras::GenesisConfig {
+					paras: vec![(chain, crate::paras::ParaGenesisArgs {
+						genesis_head: Vec::new().into(),
+						validation_code: Vec::new().into(),
+						parachain: true,
+					})],
+					..Default::default()
+				},
+				..Default::default()
+			};
+			genesis.configuration.config.chain_availability_period = 10;
+
+			genesis
+		}
+
+		/// Back a candidate of the given para in block 1 with Alice and Bob as the active
+		/// validators, and start block 2, in which the candidate occupies core 0.
+		///
+		/// Returns the parent header to build the inherent data of block 2 on.
+		fn occupy_core_with_pending_candidate(chain: ParaId) -> Header {
+			use crate::initializer::SessionChangeNotification;
+
+			let header = start_block::<Test>();
+
+			let validators = vec![Sr25519Keyring::Alice, Sr25519Keyring::Bob]
+				.into_iter()
+				.map(|v| ValidatorId::from(v.public()))
+				.collect::<Vec<_>>();
+			<shared::Pallet<Test>>::set_active_validators_ascending(validators.clone());
+			<scheduler::Module<Test>>::initializer_on_new_session(&SessionChangeNotification {
+				validators,
+				new_config: <configuration::Pallet<Test>>::config(),
+				..Default::default()
+			});
+			<scheduler::Module<Test>>::schedule(Vec::new(), 1);
+
+			let data = ParachainInherentDataBuilder::<Test>::new()
+				.with_backed_candidate(chain)
+				.build(header.clone());
+			Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+			assert!(<inclusion::Pallet<Test>>::pending_availability(chain).is_some());
+
+			let header = Header {
+				parent_hash: header.hash(),
+				number: 1,
+				..default_header()
+			};
+			Included::<Test>::kill();
+			System::set_block_number(2);
+			System::set_parent_hash(header.hash());
+
+			header
+		}
+
+		#[test]
+		fn drops_duplicate_bitfields() {
+			let chain = ParaId::from(1);
+			new_test_ext(genesis_with_para(chain)).execute_with(|| {
+				let header = occupy_core_with_pending_candidate(chain);
+
+				// only Alice's first bitfield votes for the availability of the candidate.
+				let data = ParachainInherentDataBuilder::<Test>::new()
+					.with_bitfield(ValidatorIndex(0), bitvec::bitvec![BitOrderLsb0, u8; 1; 1])
+					.with_bitfield(ValidatorIndex(0), bitvec::bitvec![BitOrderLsb0, u8; 0; 1])
+					.with_bitfield(ValidatorIndex(1), bitvec::bitvec![BitOrderLsb0, u8; 0; 1])
+					.build(header);
+
+				Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+				assert!(Included::<Test>::exists());
+				// a single vote of two validators is below the availability threshold.
+				let pending = <inclusion::Pallet<Test>>::pending_availability(chain).unwrap();
+				assert_eq!(
+					pending.availability_votes(),
+					&bitvec::bitvec![BitOrderLsb0, u8; 1, 0],
+				);
+			});
+		}
+
+		#[test]
+		fn rejects_unordered_bitfields() {
+			let chain = ParaId::from(1);
+			new_test_ext(genesis_with_para(chain)).execute_with(|| {
+				let header = occupy_core_with_pending_candidate(chain);
+
+				let data = ParachainInherentDataBuilder::<Test>::new()
+					.with_bitfield(ValidatorIndex(1), bitvec::bitvec![BitOrderLsb0, u8; 1; 1])
+					.with_bitfield(ValidatorIndex(0), bitvec::bitvec![BitOrderLsb0, u8; 1; 1])
+					.build(header);
+
+				let err = Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap_err();
+
+				assert_eq!(err.error, inclusion::Error::<Test>::BitfieldDuplicateOrUnordered.into());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn drops_candidates_rejected_by_the_filter() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				// the candidate's para isn't scheduled, so processing it would fail.
+				RejectedCandidatePara::set(Some(ParaId::from(0)));
+				let result = Call::<Test>::enter(ParachainsInherentData {
+					backed_candidates: vec![BackedCandidate::default()],
+					..data
+				})
+					.dispatch_bypass_filter(None.into());
+				RejectedCandidatePara::set(None);
+
+				assert!(result.is_ok());
+				assert!(Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn notes_last_included_block_number() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				assert_eq!(Pallet::<Test>::last_included_block_number(), None);
+
+				Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+				assert_eq!(Pallet::<Test>::last_included_block_number(), Some(1));
+			});
+		}
+
+		#[test]
+		fn overwrites_last_backed_candidate_hashes() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				LastBackedCandidateHashes::<Test>::put(vec![CandidateHash(Default::default())]);
+
+				Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+				assert!(Pallet::<Test>::last_backed_candidate_hashes().is_empty());
+			});
+		}
+
+		#[test]
+		fn filters_dispute_data() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				// there is no session info for the disputed session, which the disputes handler
+				// would reject. The statement set is filtered out instead of failing the inherent.
+				Call::<Test>::enter(ParachainsInherentData {
+					disputes: vec![DisputeStatementSet {
+						candidate_hash: CandidateHash(Default::default()),
+						session: 1,
+						statements: Vec::new(),
+					}],
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap();
+
+				assert!(Included::<Test>::exists());
+			});
+		}
+
+		/// Make Alice the only validator of the genesis session, and get a statement set in which
+		/// she votes for the validity of a candidate which is not included. The set is a fresh,
+		/// single-sided dispute which survives filtering.
+		pub(super) fn alice_dispute_statement_set() -> DisputeStatementSet {
+			alice_dispute_statement_set_about(CandidateHash(sp_core::H256::repeat_byte(1)))
+		}
+
+		/// Enact a session with Alice as the only validator and get her statement on the validity
+		/// of the given candidate.
+		pub(super) fn alice_dispute_statement_set_about(
+			candidate_hash: CandidateHash,
+		) -> DisputeStatementSet {
+			crate::mock::Initializer::test_trigger_on_new_session(
+				false,
+				0,
+				vec![(&0, Sr25519Keyring::Alice.public().into())].into_iter(),
+				None,
+			);
+
+			let signature = Sr25519Keyring::Alice.sign(&ExplicitDisputeStatement {
+				valid: true,
+				candidate_hash,
+				session: 0,
+			}.signing_payload());
+
+			DisputeStatementSet {
+				candidate_hash,
+				session: 0,
+				statements: vec![(
+					DisputeStatement::Valid(ValidDisputeStatementKind::Explicit),
+					ValidatorIndex(0),
+					signature.into(),
+				)],
+			}
+		}
+
+		#[test]
+		fn surfaces_dispute_data_errors() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let disputes = vec![alice_dispute_statement_set()];
+				let mut filtered = disputes.clone();
+				<Test as inclusion::Config>::DisputesHandler::filter_multi_dispute_data(&mut filtered);
+				assert_eq!(filtered, disputes);
+
+				DisputeDataError::set(Some(DispatchError::Other("rejected")));
+				let err = Call::<Test>::enter(ParachainsInherentData {
+					disputes,
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap_err();
+				DisputeDataError::set(None);
+
+				assert_eq!(err.error, DispatchError::Other("rejected"));
+				// the block is still missing the inherent.
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn filtering_does_not_occupy_spam_slots() {
+			let mut genesis = MockGenesisConfig::default();
+			genesis.configuration.config.dispute_max_spam_slots = 1;
+
+			new_test_ext(genesis).execute_with(|| {
+				let data = new_block_data();
+
+				// Alice may occupy a single spam slot, which the dispute takes. Had filtering
+				// taken it already, the dispute would be rejected as potential spam.
+				Call::<Test>::enter(ParachainsInherentData {
+					disputes: vec![alice_dispute_statement_set()],
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap();
+
+				assert!(Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn rejects_too_many_dispute_statements() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let statement = (
+					DisputeStatement::Valid(ValidDisputeStatementKind::Explicit),
+					ValidatorIndex(0),
+					sp_core::sr25519::Signature([0u8; 64]).into(),
+				);
+				let disputes = vec![
+					DisputeStatementSet {
+						candidate_hash: CandidateHash(Default::default()),
+						session: 1,
+						statements: vec![statement.clone(), statement.clone()],
+					},
+					DisputeStatementSet {
+						candidate_hash: CandidateHash(sp_core::H256::repeat_byte(1)),
+						session: 1,
+						statements: vec![statement],
+					},
+				];
+
+				MaxDisputeStatementsPerBlock::set(2);
+				let result = Call::<Test>::enter(ParachainsInherentData {
+					disputes,
+					..data
+				})
+					.dispatch_bypass_filter(None.into());
+				MaxDisputeStatementsPerBlock::set(1_000);
+
+				assert_eq!(result.unwrap_err().error, Error::<Test>::TooManyDisputeStatements.into());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn accounts_for_dispatched_upward_messages() {
+			use frame_support::weights::GetDispatchInfo;
+
+			let para = ParaId::from(1);
+			let msg = vec![1, 2, 3];
+
+			let genesis = MockGenesisConfig {
+				configuration: crate::configuration::GenesisConfig {
+					config: crate::configuration::HostConfiguration {
+						ump_service_total_weight: 1_000,
+						..Default::default()
+					},
+				},
+				..Default::default()
+			};
+
+			new_test_ext(genesis).execute_with(|| {
+				let data = new_block_data();
+
+				let _ = <ump::Pallet<Test>>::receive_upward_messages(para, vec![msg.clone()]);
+
+				let mut probe = crate::ump::mock_sink::Probe::new();
+				probe.assert_msg(para, msg, 500);
+
+				let call = Call::<Test>::enter(data);
+				let dispatch_info = call.get_dispatch_info();
+				let post_info = call.dispatch_bypass_filter(None.into()).unwrap();
+
+				// the whole upward message budget is claimed up front...
+				assert_eq!(dispatch_info.weight, base_weight() + 1_000);
+				// ...and only the weight actually consumed by the dispatched message is charged.
+				assert_eq!(
+					post_info.calc_actual_weight(&dispatch_info),
+					base_weight() + 500,
+				);
+			});
+		}
+	}
+
+	mod try_enter {
+		use super::*;
 
 		use frame_support::traits::UnfilteredDispatchable;
 
-		fn default_header() -> Header {
-			Header {
-				parent_hash: Default::default(),
-				number: 0,
-				state_root: Default::default(),
-				extrinsics_root: Default::default(),
-				digest: Default::default(),
+		#[test]
+		fn fails_outside_of_test_mode() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let error = Call::<Test>::try_enter(data)
+					.dispatch_bypass_filter(Some(1).into()).unwrap_err();
+
+				// The full declared weight is charged.
+				assert_eq!(error.post_info.actual_weight, None);
+				assert_eq!(error.error, Error::<Test>::TestModeDisabled.into());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn enters_the_inherent_in_test_mode() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				ParasInherentTestMode::set(true);
+				let result = Call::<Test>::try_enter(data)
+					.dispatch_bypass_filter(Some(1).into());
+				ParasInherentTestMode::set(false);
+
+				assert_eq!(result.unwrap().actual_weight, Some(base_weight()));
+				assert!(Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn requires_a_signed_origin() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				ParasInherentTestMode::set(true);
+				let result = Call::<Test>::try_enter(data)
+					.dispatch_bypass_filter(None.into());
+				ParasInherentTestMode::set(false);
+
+				assert_eq!(result.unwrap_err().error, DispatchError::BadOrigin);
+				assert!(!Included::<Test>::exists());
+			});
+		}
+	}
+
+	mod check_inherent {
+		use super::*;
+
+		use crate::configuration::HostConfiguration;
+		use primitives::v1::{
+			AvailabilityBitfield, UncheckedSigned, UncheckedSignedAvailabilityBitfield,
+			ValidatorIndex,
+		};
+
+		fn unchecked_bitfield(validator_index: u32) -> UncheckedSignedAvailabilityBitfield {
+			UncheckedSigned::new(
+				AvailabilityBitfield(Default::default()),
+				ValidatorIndex(validator_index),
+				sp_core::sr25519::Signature([0u8; 64]).into(),
+			)
+		}
+
+		fn enter(bitfields: Vec<UncheckedSignedAvailabilityBitfield>, parent_header: Header) -> Call<Test> {
+			Call::enter(ParachainsInherentData {
+				bitfields,
+				backed_candidates: Vec::new(),
+				disputes: Vec::new(),
+				parent_header,
+			})
+		}
+
+		fn genesis_with_max_validators(max_validators: Option<u32>) -> MockGenesisConfig {
+			MockGenesisConfig {
+				configuration: crate::configuration::GenesisConfig {
+					config: HostConfiguration {
+						max_validators,
+						.. Default::default()
+					},
+					.. Default::default()
+				},
+				.. Default::default()
 			}
 		}
 
+		#[test]
+		fn accepts_plausible_inherent_without_data() {
+			new_test_ext(genesis_with_max_validators(Some(2))).execute_with(|| {
+				let call = enter(vec![unchecked_bitfield(0), unchecked_bitfield(1)], default_header());
+				assert!(Pallet::<Test>::check_inherent(&call, &InherentData::new()).is_ok());
+			});
+		}
+
+		#[test]
+		fn rejects_more_bitfields_than_max_validators() {
+			new_test_ext(genesis_with_max_validators(Some(1))).execute_with(|| {
+				let call = enter(vec![unchecked_bitfield(0), unchecked_bitfield(1)], default_header());
+				assert!(Pallet::<Test>::check_inherent(&call, &InherentData::new()).is_err());
+			});
+
+			// without a maximum, any number of bitfields is plausible.
+			new_test_ext(genesis_with_max_validators(None)).execute_with(|| {
+				let call = enter(vec![unchecked_bitfield(0), unchecked_bitfield(1)], default_header());
+				assert!(Pallet::<Test>::check_inherent(&call, &InherentData::new()).is_ok());
+			});
+		}
+
+		#[test]
+		fn rejects_unexpected_parent_header() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let mut data = InherentData::new();
+				data.put_data(PARACHAINS_INHERENT_IDENTIFIER, &ParachainsInherentData {
+					bitfields: Vec::new(),
+					backed_candidates: Vec::new(),
+					disputes: Vec::new(),
+					parent_header: default_header(),
+				}).unwrap();
+
+				let call = enter(Vec::new(), default_header());
+				assert!(Pallet::<Test>::check_inherent(&call, &data).is_ok());
+
+				let mut other_header = default_header();
+				other_header.number = 1;
+				let call = enter(Vec::new(), other_header);
+				assert!(Pallet::<Test>::check_inherent(&call, &data).is_err());
+			});
+		}
+	}
+
+	mod genesis {
+		use super::*;
+
+		#[test]
+		fn pre_included_rejects_the_inherent() {
+			let mut genesis = MockGenesisConfig::default();
+			genesis.paras_inherent.pre_included = true;
+
+			new_test_ext(genesis).execute_with(|| {
+				let data = new_block_data();
+
+				let result = Pallet::<Test>::enter(frame_system::RawOrigin::None.into(), data);
+
+				assert_eq!(
+					result.map_err(|e| e.error),
+					Err(Error::<Test>::TooManyInclusionInherents.into()),
+				);
+			});
+		}
+
+		#[test]
+		fn not_included_by_default() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				assert!(!Included::<Test>::exists());
+			});
+		}
+	}
+
+	mod dry_run_enter {
+		use super::*;
+
+		#[test]
+		fn does_not_persist_storage_changes() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let weight = Pallet::<Test>::dry_run_enter(data).unwrap();
+
+				assert_eq!(weight, base_weight());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn reports_dispatch_errors() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+				System::set_parent_hash(Default::default());
+
+				let result = Pallet::<Test>::dry_run_enter(data);
+
+				assert_eq!(result, Err(Error::<Test>::InvalidParentHeader.into()));
+			});
+		}
+	}
+
+	mod scheduled_cores_count {
+		use super::*;
+
+		use keyring::Sr25519Keyring;
+		use primitives::v1::ValidatorId;
+		use crate::{initializer::SessionChangeNotification, paras::ParaGenesisArgs};
+
+		#[test]
+		fn counts_scheduled_cores() {
+			let para_genesis = ParaGenesisArgs {
+				genesis_head: Vec::new().into(),
+				validation_code: Vec::new().into(),
+				parachain: true,
+			};
+			let genesis = MockGenesisConfig {
+				paras: crate::paras::GenesisConfig {
+					paras: vec![(1.into(), para_genesis.clone()), (2.into(), para_genesis)],
+					..Default::default()
+				},
+				..Default::default()
+			};
+
+			new_test_ext(genesis).execute_with(|| {
+				assert_eq!(Pallet::<Test>::scheduled_cores_count(), 0);
+
+				let validators = vec![Sr25519Keyring::Alice, Sr25519Keyring::Bob]
+					.into_iter()
+					.map(|v| ValidatorId::from(v.public()))
+					.collect::<Vec<_>>();
+				<shared::Pallet<Test>>::set_active_validators_ascending(validators.clone());
+				<scheduler::Module<Test>>::initializer_on_new_session(&SessionChangeNotification {
+					validators,
+					new_config: <configuration::Pallet<Test>>::config(),
+					..Default::default()
+				});
+				<scheduler::Module<Test>>::schedule(Vec::new(), 1);
+
+				assert_eq!(Pallet::<Test>::scheduled_cores_count(), 2);
+			});
+		}
+	}
+
+	mod paras_inherent_weight {
+		use super::*;
+
+		use crate::mock::{
+			new_test_ext, System, MockGenesisConfig, Test, Event as MockEvent,
+			ParasInherentWeightRefundPolicy,
+		};
+
+		use frame_support::traits::{OnInitialize, UnfilteredDispatchable};
+
 		/// We expect the weight of the paras inherent not to change when no truncation occurs:
 		/// its weight is dynamically computed from the size of the backed candidates list, and is
 		/// already incorporated into the current block weight when it is selected by the provisioner.
 		#[test]
 		fn weight_does_not_change_on_happy_path() {
 			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
-				let header = default_header();
-				System::set_block_number(1);
-				System::set_parent_hash(header.hash());
+				let data = new_block_data();
 
 				// number of bitfields doesn't affect the paras inherent weight, so we can mock it with an empty one
 				let signed_bitfields = Vec::new();
@@ -421,8 +2108,8 @@ mod tests {
 				let backed_candidates = vec![BackedCandidate::default(); 10];
 
 				// the expected weight can always be computed by this formula
-				let expected_weight = MINIMAL_INCLUSION_INHERENT_WEIGHT +
-					(backed_candidates.len() as Weight * BACKED_CANDIDATE_WEIGHT);
+				let expected_weight = base_weight() +
+					(backed_candidates.len() as Weight * backed_candidate_weight());
 
 				// we've used half the block weight; there's plenty of margin
 				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
@@ -433,8 +2120,7 @@ mod tests {
 				let post_info = Call::<Test>::enter(ParachainsInherentData {
 					bitfields: signed_bitfields,
 					backed_candidates,
-					disputes: Vec::new(),
-					parent_header: default_header(),
+					..data
 				})
 					.dispatch_bypass_filter(None.into()).unwrap_err().post_info;
 
@@ -457,9 +2143,7 @@ mod tests {
 		#[test]
 		fn weight_changes_when_backed_candidates_are_truncated() {
 			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
-				let header = default_header();
-				System::set_block_number(1);
-				System::set_parent_hash(header.hash());
+				let data = new_block_data();
 
 				// number of bitfields doesn't affect the paras inherent weight, so we can mock it with an empty one
 				let signed_bitfields = Vec::new();
@@ -467,19 +2151,19 @@ mod tests {
 				let backed_candidates = vec![BackedCandidate::default(); 10];
 
 				// the expected weight with no blocks is just the minimum weight
-				let expected_weight = MINIMAL_INCLUSION_INHERENT_WEIGHT;
+				let expected_weight = base_weight();
 
 				// oops, looks like this mandatory call pushed the block weight over the limit
+				// by at least the weight of all backed candidates
 				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
-				let used_block_weight = max_block_weight + 1;
+				let used_block_weight = max_block_weight + 10 * backed_candidate_weight();
 				System::set_block_consumed_resources(used_block_weight, 0);
 
 				// execute the paras inherent
 				let post_info = Call::<Test>::enter(ParachainsInherentData {
 					bitfields: signed_bitfields,
 					backed_candidates,
-					disputes: Vec::new(),
-					parent_header: header,
+					..data
 				})
 					.dispatch_bypass_filter(None.into()).unwrap();
 
@@ -490,6 +2174,399 @@ mod tests {
 					post_info.actual_weight.unwrap(),
 					expected_weight,
 				);
+				assert_eq!(Pallet::<Test>::total_backed_candidate_weight(), 0);
+
+				assert_eq!(
+					System::events().last().map(|record| record.event.clone()),
+					Some(MockEvent::ParaInherent(Event::InherentProcessed(InherentOutcome::Truncated {
+						proposed: 10,
+						accepted: 0,
+						reason: TruncationReason::BlockWeight,
+					}))),
+				);
+			});
+		}
+
+		#[test]
+		fn weight_is_not_refunded_on_truncation_if_configured() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				System::set_block_consumed_resources(max_block_weight + 10 * backed_candidate_weight(), 0);
+
+				ParasInherentWeightRefundPolicy::set(WeightRefundPolicy::NeverRefundOnTruncation);
+				let result = Call::<Test>::enter(ParachainsInherentData {
+					backed_candidates: vec![BackedCandidate::default(); 10],
+					..data
+				})
+					.dispatch_bypass_filter(None.into());
+				ParasInherentWeightRefundPolicy::set(WeightRefundPolicy::AlwaysRefund);
+
+				// the pre-computed weight of all proposed candidates is charged.
+				assert_eq!(result.unwrap().actual_weight, None);
+				assert!(Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		fn truncation_deposits_an_event() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				System::set_block_consumed_resources(max_block_weight + 4 * backed_candidate_weight(), 0);
+
+				Call::<Test>::enter(ParachainsInherentData {
+					backed_candidates: vec![BackedCandidate::default(); 4],
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap();
+
+				let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+				assert!(events.iter().any(|event| matches!(
+					event,
+					MockEvent::ParaInherent(Event::InherentProcessed(InherentOutcome::Truncated {
+						proposed: 4,
+						accepted: 0,
+						..
+					})),
+				)));
+				assert!(!events.iter().any(|event| matches!(
+					event,
+					MockEvent::ParaInclusion(inclusion::Event::CandidateBacked(..)),
+				)));
+			});
+		}
+
+		#[test]
+		fn dispatched_upward_messages_deposit_an_event() {
+			let mut genesis = MockGenesisConfig::default();
+			genesis.configuration.config.ump_service_total_weight = 1000;
+
+			new_test_ext(genesis).execute_with(|| {
+				let data = new_block_data();
+
+				<ump::Pallet<Test>>::receive_upward_messages(1.into(), vec![vec![1], vec![2]]);
+
+				Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+				let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+				assert!(events.contains(&MockEvent::ParaInherent(Event::UpwardMessagesProcessed(2, 0))));
+			});
+		}
+
+		#[test]
+		fn block_author_is_rewarded_for_backed_candidates() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let max_block_weight = <Test as frame_system::Config>::BlockWeights::get().max_block;
+				System::set_block_consumed_resources(max_block_weight + 4 * backed_candidate_weight(), 0);
+
+				Call::<Test>::enter(ParachainsInherentData {
+					backed_candidates: vec![BackedCandidate::default(); 4],
+					..data
+				})
+					.dispatch_bypass_filter(None.into()).unwrap();
+
+				// all candidates were truncated, so none were backed.
+				assert_eq!(crate::mock::author_backing_rewards(), vec![(crate::mock::BLOCK_AUTHOR, 0)]);
+			});
+		}
+
+		#[test]
+		fn per_block_statistics_are_cleared_on_initialize() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				TotalBackedCandidateWeight::<Test>::put(10 * backed_candidate_weight());
+				FreedByDispute::<Test>::put(1);
+				FreedByConcluded::<Test>::put(2);
+				FreedByTimeout::<Test>::put(3);
+
+				<Pallet<Test> as OnInitialize<_>>::on_initialize(2);
+
+				assert_eq!(Pallet::<Test>::total_backed_candidate_weight(), 0);
+				assert_eq!(Pallet::<Test>::freed_by_dispute(), 0);
+				assert_eq!(Pallet::<Test>::freed_by_concluded(), 0);
+				assert_eq!(Pallet::<Test>::freed_by_timeout(), 0);
+			});
+		}
+
+		/// The claimed weight accounts for the erasure chunks claimed by all bitfields, counting
+		/// only the bits of existing availability cores.
+		#[test]
+		fn claimed_weight_includes_erasure_chunks() {
+			use frame_support::weights::GetDispatchInfo;
+			use keyring::Sr25519Keyring;
+			use primitives::v1::{
+				AvailabilityBitfield, UncheckedSigned, ValidatorId, ValidatorIndex,
+			};
+			use crate::{initializer::SessionChangeNotification, paras::ParaGenesisArgs};
+
+			let para_genesis = ParaGenesisArgs {
+				genesis_head: Vec::new().into(),
+				validation_code: Vec::new().into(),
+				parachain: true,
+			};
+			let genesis = MockGenesisConfig {
+				paras: crate::paras::GenesisConfig {
+					paras: vec![(1.into(), para_genesis.clone()), (2.into(), para_genesis)],
+					..Default::default()
+				},
+				..Default::default()
+			};
+
+			new_test_ext(genesis).execute_with(|| {
+				let validators = vec![Sr25519Keyring::Alice, Sr25519Keyring::Bob]
+					.into_iter()
+					.map(|v| ValidatorId::from(v.public()))
+					.collect::<Vec<_>>();
+				<shared::Pallet<Test>>::set_active_validators_ascending(validators.clone());
+				<scheduler::Module<Test>>::initializer_on_new_session(&SessionChangeNotification {
+					validators,
+					new_config: <configuration::Pallet<Test>>::config(),
+					..Default::default()
+				});
+				assert_eq!(<scheduler::Module<Test>>::availability_cores().len(), 2);
+
+				let bitfield = |validator_index, bits| UncheckedSigned::new(
+					AvailabilityBitfield(bits),
+					ValidatorIndex(validator_index),
+					sp_core::sr25519::Signature([0u8; 64]).into(),
+				);
+				// the third bit of each bitfield is beyond the availability cores.
+				let bitfields = vec![
+					bitfield(0, bitvec::bitvec![bitvec::order::Lsb0, u8; 1, 0, 1]),
+					bitfield(1, bitvec::bitvec![bitvec::order::Lsb0, u8; 0, 0, 0]),
+					bitfield(2, bitvec::bitvec![bitvec::order::Lsb0, u8; 1, 1, 1]),
+				];
+				assert_eq!(erasure_chunk_weight::<Test>(&bitfields), 3 * ERASURE_CHUNK_WEIGHT);
+
+				let dispatch_info = Call::<Test>::enter(ParachainsInherentData {
+					bitfields,
+					backed_candidates: vec![BackedCandidate::default(); 2],
+					disputes: Vec::new(),
+					parent_header: default_header(),
+				}).get_dispatch_info();
+
+				assert_eq!(
+					dispatch_info.weight,
+					base_weight() + 2 * backed_candidate_weight() + 3 * ERASURE_CHUNK_WEIGHT +
+						<configuration::Pallet<Test>>::config().ump_service_total_weight,
+				);
+			});
+		}
+
+		/// The claimed weight accounts for all dispute statement sets, while the actual weight
+		/// only accounts for those left after filtering.
+		#[test]
+		fn weight_includes_disputes() {
+			use frame_support::weights::GetDispatchInfo;
+
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				// there is no session info for the disputed session, so the set is filtered out.
+				let call = Call::<Test>::enter(ParachainsInherentData {
+					disputes: vec![primitives::v1::DisputeStatementSet {
+						candidate_hash: CandidateHash(Default::default()),
+						session: 1,
+						statements: Vec::new(),
+					}],
+					..data
+				});
+				assert_eq!(call.get_dispatch_info().weight, base_weight() + dispute_weight());
+
+				let post_info = call.dispatch_bypass_filter(None.into()).unwrap();
+				assert_eq!(post_info.actual_weight, Some(base_weight()));
+			});
+		}
+
+		/// If the chain is frozen due to disputes, no parachain work is done and only the weight
+		/// measured by the `enter_with_disputes` benchmark is charged.
+		#[test]
+		fn only_minimal_and_dispute_weight_is_charged_when_frozen() {
+			use frame_support::weights::GetDispatchInfo;
+			use sp_core::H256;
+
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				let disputes = vec![
+					super::enter::alice_dispute_statement_set_about(CandidateHash(H256::repeat_byte(1))),
+					super::enter::alice_dispute_statement_set_about(CandidateHash(H256::repeat_byte(2))),
+				];
+
+				crate::disputes::Pallet::<Test>::revert_and_freeze(0);
+				assert!(<Test as inclusion::Config>::DisputesHandler::is_frozen());
+
+				// these candidates would fail to be processed, as their para isn't scheduled.
+				let call = Call::<Test>::enter(ParachainsInherentData {
+					backed_candidates: vec![BackedCandidate::default(); 10],
+					disputes,
+					..data
+				});
+				let claimed_weight = call.get_dispatch_info().weight;
+				let post_info = call.dispatch_bypass_filter(None.into()).unwrap();
+
+				let frozen_weight = <Test as Config>::WeightInfo::enter_with_disputes(2);
+				assert_eq!(frozen_weight, base_weight() + 2 * dispute_weight());
+				assert_eq!(post_info.actual_weight, Some(frozen_weight));
+				assert!(frozen_weight < claimed_weight);
+				assert!(Included::<Test>::exists());
+				assert_eq!(Pallet::<Test>::last_included_block_number(), Some(1));
+				assert_eq!(Pallet::<Test>::total_backed_candidate_weight(), 0);
+				assert!(!System::events().into_iter().any(|record| matches!(
+					record.event,
+					MockEvent::ParaInclusion(inclusion::Event::CandidateBacked(..)),
+				)));
+			});
+		}
+	}
+
+	#[test]
+	fn errors_are_displayed_as_messages() {
+		assert_eq!(
+			Error::<Test>::TooManyBitfields.to_string(),
+			"there are more signed bitfields than active validators",
+		);
+		assert_eq!(
+			Error::<Test>::InvalidParentHeader.to_string(),
+			"the parent header does not match the hash of the parent block",
+		);
+	}
+
+	mod on_finalize {
+		use super::*;
+
+		use crate::mock::{Event as MockEvent, PanicOnMissingInherent};
+		use frame_support::traits::{OnFinalize, UnfilteredDispatchable};
+
+		#[test]
+		fn missing_inherent_invokes_handler() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				System::set_block_number(1);
+
+				<Pallet<Test> as OnFinalize<_>>::on_finalize(1);
+
+				assert_eq!(
+					System::events().pop().expect("should have an event").event,
+					MockEvent::ParaInherent(Event::InherentMissing),
+				);
+			});
+		}
+
+		#[test]
+		fn included_inherent_does_not_invoke_handler() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				System::set_block_number(1);
+				Included::<Test>::set(Some(()));
+
+				<Pallet<Test> as OnFinalize<_>>::on_finalize(1);
+
+				assert!(System::events().is_empty());
+				assert!(!Included::<Test>::exists());
+			});
+		}
+
+		#[test]
+		#[should_panic(expected = "Bitfields and heads must be included every block")]
+		fn default_handler_panics() {
+			<() as MissingInherentHandler>::handle();
+		}
+
+		#[test]
+		fn missing_inherent_panics_with_the_default_handler() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				System::set_block_number(1);
+
+				PanicOnMissingInherent::set(true);
+				let result = std::panic::catch_unwind(|| {
+					<Pallet<Test> as OnFinalize<_>>::on_finalize(1);
+				});
+				PanicOnMissingInherent::set(false);
+
+				let panic = result.expect_err("finalizing without the inherent must panic");
+				let message = panic.downcast_ref::<&str>().expect("panics with a static message");
+				assert!(message.contains("Bitfields and heads must be included every block"));
+			});
+		}
+
+		#[test]
+		fn entered_inherent_prevents_the_panic() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				let data = new_block_data();
+
+				PanicOnMissingInherent::set(true);
+				Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+				let result = std::panic::catch_unwind(|| {
+					<Pallet<Test> as OnFinalize<_>>::on_finalize(1);
+				});
+				PanicOnMissingInherent::set(false);
+
+				assert!(result.is_ok());
+			});
+		}
+	}
+
+	mod migrations {
+		use super::*;
+
+		use frame_support::traits::{GetStorageVersion, StorageVersion};
+
+		fn db_weight() -> frame_support::weights::RuntimeDbWeight {
+			<Test as frame_system::Config>::DbWeight::get()
+		}
+
+		#[test]
+		fn v1_initializes_new_storage_items() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				StorageVersion::new(0).put::<Pallet<Test>>();
+				Included::<Test>::set(Some(()));
+				FreedByDispute::<Test>::put(3);
+				TotalBackedCandidateWeight::<Test>::put(10);
+
+				frame_support::migration::put_storage_value(b"ParaInherent", b"Included", b"", ());
+
+				let weight = migrations::v1::migrate::<Test>();
+
+				assert_eq!(weight, db_weight().reads_writes(1, 7));
+				assert_eq!(Pallet::<Test>::on_chain_storage_version(), 1);
+				assert!(!Included::<Test>::exists());
+				assert_eq!(
+					frame_support::migration::get_storage_value::<()>(b"ParaInherent", b"Included", b""),
+					None,
+				);
+				assert_eq!(FreedByDispute::<Test>::get(), 0);
+				assert_eq!(TotalBackedCandidateWeight::<Test>::get(), 0);
+			});
+		}
+
+		#[cfg(feature = "try-runtime")]
+		#[test]
+		fn v1_passes_the_try_runtime_checks() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				StorageVersion::new(0).put::<Pallet<Test>>();
+				frame_support::migration::put_storage_value(b"ParaInherent", b"Included", b"", ());
+				FreedByTimeout::<Test>::put(1);
+
+				assert_eq!(migrations::v1::pre_migrate::<Test>(), Ok(()));
+				migrations::v1::migrate::<Test>();
+				assert_eq!(migrations::v1::post_migrate::<Test>(), Ok(()));
+				assert!(migrations::v1::pre_migrate::<Test>().is_err());
+			});
+		}
+
+		#[test]
+		fn v1_is_skipped_when_already_applied() {
+			new_test_ext(MockGenesisConfig::default()).execute_with(|| {
+				StorageVersion::new(1).put::<Pallet<Test>>();
+				FreedByDispute::<Test>::put(3);
+
+				let weight = migrations::v1::migrate::<Test>();
+
+				assert_eq!(weight, db_weight().reads(1));
+				assert_eq!(FreedByDispute::<Test>::get(), 3);
 			});
 		}
 	}
@@ -19,15 +19,20 @@
 
 use sp_std::prelude::*;
 use sp_std::collections::btree_map::BTreeMap;
-use sp_runtime::traits::One;
+use sp_runtime::{traits::One, DispatchError};
 use primitives::v1::{
-	AuthorityDiscoveryId, CandidateEvent, CommittedCandidateReceipt, CoreIndex, CoreOccupied,
+	AuthorityDiscoveryId, CandidateEvent, CandidateHash, CommittedCandidateReceipt, CoreIndex, CoreOccupied,
 	CoreState, GroupIndex, GroupRotationInfo, Id as ParaId, InboundDownwardMessage,
+	InherentData as ParachainsInherentData,
 	InboundHrmpMessage, OccupiedCore, OccupiedCoreAssumption, PersistedValidationData,
 	ScheduledCore, SessionIndex, SessionInfo, ValidationCode, ValidationCodeHash, ValidatorId,
 	ValidatorIndex,
 };
-use crate::{initializer, inclusion, scheduler, configuration, paras, session_info, dmp, hrmp, shared};
+use frame_support::weights::Weight;
+use crate::{
+	initializer, inclusion, scheduler, configuration, paras, paras_inherent, session_info, dmp,
+	hrmp, shared,
+};
 
 
 /// Implementation for the `validators` function of the runtime API.
@@ -331,3 +336,45 @@ pub fn validation_code_by_hash<T: paras::Config>(
 ) -> Option<ValidationCode> {
 	<paras::Pallet<T>>::code_by_hash(hash)
 }
+
+/// Implementation for the `last_backed_candidate_weight` function of the runtime API.
+pub fn last_backed_candidate_weight<T: paras_inherent::Config>() -> Weight {
+	<paras_inherent::Pallet<T>>::total_backed_candidate_weight()
+}
+
+/// Implementation for the `dry_run_inherent` function of the runtime API.
+pub fn dry_run_inherent<T: paras_inherent::Config>(
+	data: ParachainsInherentData<T::Header>,
+) -> Result<Weight, DispatchError> {
+	<paras_inherent::Pallet<T>>::dry_run_enter(data)
+}
+
+/// Implementation for the `pending_availability_count` function of the runtime API.
+pub fn pending_availability_count<T: paras_inherent::Config>() -> u32 {
+	<paras_inherent::Pallet<T>>::pending_availability_count()
+}
+
+/// Implementation for the `pending_bitfields` function of the runtime API.
+pub fn pending_bitfields<T: paras_inherent::Config>() -> Vec<ValidatorIndex> {
+	<paras_inherent::Pallet<T>>::pending_bitfields()
+}
+
+/// Implementation for the `last_included_block_number` function of the runtime API.
+pub fn last_included_block_number<T: paras_inherent::Config>() -> Option<T::BlockNumber> {
+	<paras_inherent::Pallet<T>>::last_included_block_number()
+}
+
+/// Implementation for the `scheduled_cores_count` function of the runtime API.
+pub fn scheduled_cores_count<T: paras_inherent::Config>() -> u32 {
+	<paras_inherent::Pallet<T>>::scheduled_cores_count()
+}
+
+/// Implementation for the `availability_core_occupancy` function of the runtime API.
+pub fn availability_core_occupancy<T: paras_inherent::Config>() -> Vec<Option<ParaId>> {
+	<paras_inherent::Pallet<T>>::availability_core_occupancy()
+}
+
+/// Implementation for the `last_backed_candidate_hashes` function of the runtime API.
+pub fn last_backed_candidate_hashes<T: paras_inherent::Config>() -> Vec<CandidateHash> {
+	<paras_inherent::Pallet<T>>::last_backed_candidate_hashes()
+}
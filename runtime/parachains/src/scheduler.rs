@@ -95,13 +95,21 @@ impl ParathreadClaimQueue {
 }
 
 /// Reasons a core might be freed
+///
+/// The discriminants are used as a sort key when ordering freed cores, so they must stay stable.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "std", derive(PartialEq, Debug))]
+#[repr(u8)]
 pub enum FreedReason {
 	/// The core's work concluded and the parablock assigned to it is considered available.
-	Concluded,
+	Concluded = 0,
 	/// The core's work timed out.
-	TimedOut,
+	TimedOut = 1,
 }
 
+// Compile-time check that the `FreedReason` discriminants are strictly ordered.
+const _: [(); 1] = [(); (FreedReason::Concluded as u8) < (FreedReason::TimedOut as u8)) as usize];
+
 
 /// The assignment type.
 #[derive(Clone, Encode, Decode)]
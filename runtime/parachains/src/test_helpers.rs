@@ -0,0 +1,390 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for constructing valid inputs to the parachains modules in tests and benchmarks.
+//!
+//! All signatures are produced by the keystore of the externalities, which must hold the keys of
+//! the active validators.
+
+use sp_std::{marker::PhantomData, prelude::*};
+
+use bitvec::{order::Lsb0 as BitOrderLsb0, vec::BitVec};
+use parity_scale_codec::Encode;
+use primitives::v1::{
+	AvailabilityBitfield, BackedCandidate, CandidateCommitments, CandidateDescriptor, CandidateHash,
+	CollatorId, CommittedCandidateReceipt, CompactStatement, DisputeStatement, DisputeStatementSet,
+	ExplicitDisputeStatement, Id as ParaId, InherentData as ParachainsInherentData,
+	InvalidDisputeStatementKind, SessionIndex, SigningContext, UncheckedSigned,
+	ValidDisputeStatementKind, ValidatorId, ValidatorIndex, ValidatorSignature, ValidityAttestation,
+};
+use sp_runtime::{
+	traits::{Header as HeaderT, One, UniqueSaturatedInto},
+	RuntimeAppPublic,
+};
+
+use crate::{initializer, paras, paras_inherent, scheduler, shared};
+
+/// Sign the payload with the key of the active validator with the given index.
+fn sign_as_validator<T: shared::Config>(
+	validator_index: ValidatorIndex,
+	payload: &[u8],
+) -> ValidatorSignature {
+	shared::Pallet::<T>::active_validator_keys()
+		.get(validator_index.0 as usize)
+		.expect("validator index is out of bounds of the active validators")
+		.sign(&payload)
+		.expect("the keys of active validators are expected to be in the keystore")
+}
+
+/// Start block 1 on top of a default genesis header, which is returned as the parent header to
+/// build the inherent data of the block on.
+pub fn start_block<T: frame_system::Config>() -> T::Header {
+	let parent_header = T::Header::new(
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	frame_system::Pallet::<T>::set_block_number(One::one());
+	frame_system::Pallet::<T>::set_parent_hash(parent_header.hash());
+
+	parent_header
+}
+
+/// A statement of the active validator with the given index on the validity of a candidate, as
+/// included in a dispute statement set.
+pub fn dispute_statement<T: shared::Config>(
+	validator_index: ValidatorIndex,
+	candidate_hash: CandidateHash,
+	session: SessionIndex,
+	valid: bool,
+) -> (DisputeStatement, ValidatorIndex, ValidatorSignature) {
+	let statement = if valid {
+		DisputeStatement::Valid(ValidDisputeStatementKind::Explicit)
+	} else {
+		DisputeStatement::Invalid(InvalidDisputeStatementKind::Explicit)
+	};
+	let payload = ExplicitDisputeStatement { valid, candidate_hash, session }.signing_payload();
+	let signature = sign_as_validator::<T>(validator_index, &payload);
+
+	(statement, validator_index, signature)
+}
+
+/// Builds [`ParachainsInherentData`] which passes the checks of the paras inherent, signing
+/// bitfields and backing statements with the keys of the currently active validators.
+///
+/// Each backed para must be scheduled on an availability core at the time `build` is called.
+pub struct ParachainInherentDataBuilder<T: paras_inherent::Config> {
+	bitfields: Vec<(ValidatorIndex, BitVec<BitOrderLsb0, u8>)>,
+	backed_candidates: Vec<ParaId>,
+	_phantom: PhantomData<T>,
+}
+
+impl<T: paras_inherent::Config> Default for ParachainInherentDataBuilder<T> {
+	fn default() -> Self {
+		ParachainInherentDataBuilder {
+			bitfields: Vec::new(),
+			backed_candidates: Vec::new(),
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<T: paras_inherent::Config> ParachainInherentDataBuilder<T> {
+	/// Create a builder for inherent data without any bitfields or backed candidates.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Include a candidate of the given para, backed by all validators of its group.
+	pub fn with_backed_candidate(mut self, para_id: ParaId) -> Self {
+		self.backed_candidates.push(para_id);
+		self
+	}
+
+	/// Include an availability bitfield signed by the given validator.
+	pub fn with_bitfield(mut self, validator_index: ValidatorIndex, bits: BitVec<BitOrderLsb0, u8>) -> Self {
+		self.bitfields.push((validator_index, bits));
+		self
+	}
+
+	/// Build the inherent data on top of the given parent header, which is expected to be the
+	/// header of the parent of the current block.
+	pub fn build(self, parent_header: T::Header) -> ParachainsInherentData<T::Header> {
+		let signing_context = SigningContext {
+			parent_hash: parent_header.hash(),
+			session_index: shared::Pallet::<T>::session_index(),
+		};
+
+		let bitfields = self.bitfields.into_iter()
+			.map(|(validator_index, bits)| {
+				let bitfield = AvailabilityBitfield(bits);
+				let payload = (&bitfield, &signing_context).encode();
+				let signature = sign_as_validator::<T>(validator_index, &payload);
+
+				UncheckedSigned::new(bitfield, validator_index, signature)
+			})
+			.collect();
+
+		// candidates must be provided in the order of the cores they are scheduled on.
+		let scheduled = scheduler::Module::<T>::scheduled();
+		let mut assignments = self.backed_candidates.into_iter()
+			.map(|para_id| scheduled.iter()
+				.find(|assignment| assignment.para_id == para_id)
+				.cloned()
+				.expect("backed paras are expected to be scheduled")
+			)
+			.collect::<Vec<_>>();
+		assignments.sort_by_key(|assignment| assignment.core);
+
+		let backed_candidates = assignments.into_iter()
+			.map(|assignment| Self::backed_candidate(
+				assignment.para_id,
+				scheduler::Module::<T>::group_validators(assignment.group_idx)
+					.expect("scheduled cores are expected to have a validator group"),
+				&parent_header,
+				&signing_context,
+			))
+			.collect();
+
+		ParachainsInherentData {
+			bitfields,
+			backed_candidates,
+			disputes: Vec::new(),
+			parent_header,
+		}
+	}
+
+	fn backed_candidate(
+		para_id: ParaId,
+		group: Vec<ValidatorIndex>,
+		parent_header: &T::Header,
+		signing_context: &SigningContext<T::Hash>,
+	) -> BackedCandidate<T::Hash> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let relay_parent_number = now - One::one();
+		let persisted_validation_data_hash = crate::util::make_persisted_validation_data::<T>(
+			para_id,
+			relay_parent_number,
+			parent_header.state_root().clone(),
+		)
+			.expect("backed paras are expected to be registered")
+			.hash();
+		let validation_code_hash = paras::Pallet::<T>::validation_code_hash_at(para_id, now, None)
+			.expect("backed paras are expected to have validation code");
+
+		let collator = CollatorId::generate_pair(None);
+		let mut descriptor = CandidateDescriptor {
+			para_id,
+			relay_parent: signing_context.parent_hash.clone(),
+			collator: collator.clone(),
+			persisted_validation_data_hash,
+			validation_code_hash,
+			..Default::default()
+		};
+		let payload = primitives::v1::collator_signature_payload(
+			&descriptor.relay_parent,
+			&descriptor.para_id,
+			&descriptor.persisted_validation_data_hash,
+			&descriptor.pov_hash,
+			&descriptor.validation_code_hash,
+		);
+		descriptor.signature = collator.sign(&payload[..])
+			.expect("the collator key was just generated in the keystore");
+
+		let candidate = CommittedCandidateReceipt {
+			descriptor,
+			commitments: CandidateCommitments {
+				hrmp_watermark: relay_parent_number.unique_saturated_into(),
+				..Default::default()
+			},
+		};
+
+		let payload = (CompactStatement::Valid(candidate.hash()), signing_context).encode();
+		let validity_votes = group.iter()
+			.map(|validator_index| ValidityAttestation::Explicit(
+				sign_as_validator::<T>(*validator_index, &payload),
+			))
+			.collect();
+
+		BackedCandidate {
+			candidate,
+			validity_votes,
+			validator_indices: bitvec::bitvec![BitOrderLsb0, u8; 1; group.len()],
+		}
+	}
+}
+
+/// Set up the current block for a benchmark of the paras inherent and get the data of the
+/// inherent, as needed by [`paras_inherent::BenchmarkHelper::inherent_data`].
+///
+/// This registers `n_paras` parachains after any already registered ones and enacts a session
+/// with as many validators, whose keys are generated in the keystore. The data contains a
+/// bitfield of every active validator and a backed candidate of each of the first
+/// `backed_candidates` of the registered paras.
+pub fn benchmark_inherent_data<T: paras_inherent::Config + initializer::Config>(
+	n_paras: u32,
+	backed_candidates: u32,
+) -> ParachainsInherentData<T::Header> {
+	let parent_header = start_block::<T>();
+
+	// register the paras before the session change, so the availability cores don't depend on
+	// the number of candidates.
+	let first_para = paras::Pallet::<T>::parachains()
+		.last()
+		.map_or(0, |para_id| u32::from(*para_id) + 1);
+	let paras = (first_para..first_para + n_paras).map(ParaId::from).collect::<Vec<_>>();
+	for para_id in &paras {
+		paras::Pallet::<T>::schedule_para_initialize(*para_id, paras::ParaGenesisArgs {
+			genesis_head: Vec::new().into(),
+			validation_code: Vec::new().into(),
+			parachain: true,
+		}).expect("paras are not registered before the benchmark");
+	}
+	shared::Pallet::<T>::set_session_index(shared::Pallet::<T>::scheduled_session());
+	paras::Pallet::<T>::test_on_new_session();
+
+	// the genesis session is applied immediately, assigning the validators to the cores of the
+	// parachains.
+	let validators = (0..n_paras)
+		.map(|_| ValidatorId::generate_pair(None))
+		.collect::<Vec<_>>();
+	let account = T::AccountId::default();
+	initializer::Pallet::<T>::test_trigger_on_new_session(
+		false,
+		0,
+		validators.iter().map(|v| (&account, v.clone())),
+		None,
+	);
+	scheduler::Module::<T>::schedule(Vec::new(), frame_system::Pallet::<T>::block_number());
+
+	let cores = scheduler::Module::<T>::availability_cores().len();
+	let builder = (0..shared::Pallet::<T>::active_validator_keys().len())
+		.fold(ParachainInherentDataBuilder::<T>::new(), |builder, i| builder.with_bitfield(
+			ValidatorIndex(i as u32),
+			bitvec::bitvec![BitOrderLsb0, u8; 0; cores],
+		));
+	paras.into_iter()
+		.take(backed_candidates as usize)
+		.fold(builder, |builder, para_id| builder.with_backed_candidate(para_id))
+		.build(parent_header)
+}
+
+/// A set of statements opening a fresh dispute about a distinct candidate for each index, as
+/// needed by [`paras_inherent::BenchmarkHelper::fresh_dispute`].
+///
+/// Votes of the first three active validators confirm the dispute, so it doesn't occupy any spam
+/// slots, while it is far from concluding as long as there are enough active validators.
+pub fn benchmark_fresh_dispute<T: shared::Config>(
+	index: u32,
+	session: SessionIndex,
+) -> DisputeStatementSet {
+	let candidate_hash = CandidateHash(sp_core::H256::from_low_u64_be(index as u64));
+
+	DisputeStatementSet {
+		candidate_hash,
+		session,
+		statements: vec![
+			dispute_statement::<T>(ValidatorIndex(0), candidate_hash, session, true),
+			dispute_statement::<T>(ValidatorIndex(1), candidate_hash, session, true),
+			dispute_statement::<T>(ValidatorIndex(2), candidate_hash, session, false),
+		],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::traits::UnfilteredDispatchable;
+	use keyring::Sr25519Keyring;
+	use crate::{
+		inclusion,
+		initializer::SessionChangeNotification,
+		mock::{new_test_ext, Configuration, Event as MockEvent, MockGenesisConfig, ParasShared, Scheduler, System, Test},
+		paras::ParaGenesisArgs,
+	};
+
+	#[test]
+	fn builds_inherent_data_accepted_by_enter() {
+		let chain = ParaId::from(1);
+		let validators = vec![Sr25519Keyring::Alice, Sr25519Keyring::Bob];
+
+		let genesis = MockGenesisConfig {
+			paras: paras::GenesisConfig {
+				paras: vec![(chain, ParaGenesisArgs {
+					genesis_head: Vec::new().into(),
+					validation_code: Vec::new().into(),
+					parachain: true,
+				})],
+				..Default::default()
+			},
+			..Default::default()
+		};
+
+		new_test_ext(genesis).execute_with(|| {
+			let header = start_block::<Test>();
+
+			let validator_ids = validators.iter().map(|v| ValidatorId::from(v.public())).collect::<Vec<_>>();
+			ParasShared::set_active_validators_ascending(validator_ids.clone());
+			Scheduler::initializer_on_new_session(&SessionChangeNotification {
+				validators: validator_ids,
+				new_config: Configuration::config(),
+				..Default::default()
+			});
+			Scheduler::schedule(Vec::new(), 1);
+
+			let data = ParachainInherentDataBuilder::<Test>::new()
+				.with_bitfield(ValidatorIndex(0), bitvec::bitvec![BitOrderLsb0, u8; 0; 1])
+				.with_bitfield(ValidatorIndex(1), bitvec::bitvec![BitOrderLsb0, u8; 0; 1])
+				.with_backed_candidate(chain)
+				.build(header);
+			let candidate_hash = data.backed_candidates[0].hash();
+
+			paras_inherent::Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+			assert!(System::events().into_iter().any(|record| matches!(
+				record.event,
+				MockEvent::ParaInclusion(inclusion::Event::CandidateBacked(receipt, ..))
+					if receipt.descriptor.para_id == chain && receipt.hash() == candidate_hash
+			)));
+		});
+	}
+
+	#[test]
+	fn benchmark_disputes_are_imported() {
+		use crate::disputes::DisputesHandler;
+
+		new_test_ext(Default::default()).execute_with(|| {
+			let mut data = benchmark_inherent_data::<Test>(4, 0);
+			let session = ParasShared::session_index();
+			data.disputes = (0..3).map(|i| benchmark_fresh_dispute::<Test>(i, session)).collect();
+			let candidate_hashes = data.disputes.iter()
+				.map(|set| set.candidate_hash)
+				.collect::<Vec<_>>();
+
+			paras_inherent::Call::<Test>::enter(data).dispatch_bypass_filter(None.into()).unwrap();
+
+			for candidate_hash in candidate_hashes {
+				assert!(<Test as crate::inclusion::Config>::DisputesHandler::could_be_invalid(
+					session,
+					candidate_hash,
+				));
+			}
+		});
+	}
+}
@@ -385,8 +385,11 @@ impl<T: Config> Pallet<T> {
 	}
 
 	/// Devote some time into dispatching pending upward messages.
-	pub(crate) fn process_pending_upward_messages() -> Weight {
+	///
+	/// Returns the number of dispatched messages and the weight used.
+	pub(crate) fn process_pending_upward_messages() -> (u32, Weight) {
 		let mut weight_used = 0;
+		let mut dispatched = 0;
 
 		let config = <configuration::Pallet<T>>::config();
 		let mut cursor = NeedsDispatchCursor::new::<T>();
@@ -412,7 +415,10 @@ impl<T: Config> Pallet<T> {
 			let (upward_message, became_empty) = queue_cache.dequeue::<T>(dispatchee);
 			if let Some(upward_message) = upward_message {
 				match T::UmpSink::process_upward_message(dispatchee, &upward_message[..], max_weight) {
-					Ok(used) => weight_used += used,
+					Ok(used) => {
+						weight_used += used;
+						dispatched += 1;
+					},
 					Err((id, required)) => {
 						// we process messages in order and don't drop them if we run out of weight, so need to break
 						// here.
@@ -433,7 +439,7 @@ impl<T: Config> Pallet<T> {
 		cursor.flush::<T>();
 		queue_cache.flush::<T>();
 
-		weight_used
+		(dispatched, weight_used)
 	}
 }
 
@@ -836,7 +842,7 @@ mod tests {
 			assert_storage_consistency_exhaustive();
 
 			// make sure that the case with empty queues is handled properly
-			Ump::process_pending_upward_messages();
+			assert_eq!(Ump::process_pending_upward_messages(), (0, 0));
 
 			assert_storage_consistency_exhaustive();
 		});
@@ -853,7 +859,7 @@ mod tests {
 			probe.assert_msg(a, msg.clone(), 0);
 			queue_upward_msg(a, msg);
 
-			Ump::process_pending_upward_messages();
+			assert_eq!(Ump::process_pending_upward_messages(), (1, 0));
 
 			assert_storage_consistency_exhaustive();
 		});
@@ -1230,6 +1230,38 @@ sp_api::impl_runtime_apis! {
 		fn validation_code_by_hash(_hash: ValidationCodeHash) -> Option<ValidationCode> {
 			None
 		}
+
+		fn last_backed_candidate_weight() -> u64 {
+			0
+		}
+
+		fn dry_run_inherent(_data: primitives::v1::InherentData) -> Result<u64, sp_runtime::DispatchError> {
+			Err(sp_runtime::DispatchError::Other("Parachains are not enabled"))
+		}
+
+		fn pending_availability_count() -> u32 {
+			0
+		}
+
+		fn pending_bitfields() -> Vec<primitives::v1::ValidatorIndex> {
+			Vec::new()
+		}
+
+		fn last_included_block_number() -> Option<BlockNumber> {
+			None
+		}
+
+		fn scheduled_cores_count() -> u32 {
+			0
+		}
+
+		fn availability_core_occupancy() -> Vec<Option<Id>> {
+			Vec::new()
+		}
+
+		fn last_backed_candidate_hashes() -> Vec<primitives::v1::CandidateHash> {
+			Vec::new()
+		}
 	}
 
 	impl beefy_primitives::BeefyApi<Block> for Runtime {
@@ -225,7 +225,7 @@ construct_runtime! {
 		Configuration: parachains_configuration::{Pallet, Call, Storage, Config<T>},
 		ParasShared: parachains_shared::{Pallet, Call, Storage},
 		ParaInclusion: parachains_inclusion::{Pallet, Call, Storage, Event<T>},
-		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent},
+		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent, Event},
 		Scheduler: parachains_scheduler::{Pallet, Call, Storage},
 		Paras: parachains_paras::{Pallet, Call, Storage, Event, Config},
 		Initializer: parachains_initializer::{Pallet, Call, Storage},
@@ -772,7 +772,28 @@ impl parachains_hrmp::Config for Runtime {
 	type Currency = Balances;
 }
 
-impl parachains_paras_inherent::Config for Runtime {}
+parameter_types! {
+	pub const MaxCodeUpgradesPerInherent: u32 = 1;
+	pub const MaxBackedCandidatesPerPara: u32 = 1;
+	pub const MaxDisputeStatementsPerBlock: u32 = 5_000;
+	pub const ParasInherentWeightRefundPolicy: parachains_paras_inherent::WeightRefundPolicy =
+		parachains_paras_inherent::WeightRefundPolicy::AlwaysRefund;
+	pub const ParasInherentTestMode: bool = false;
+}
+
+impl parachains_paras_inherent::Config for Runtime {
+	type Event = Event;
+	type MaxCodeUpgradesPerInherent = MaxCodeUpgradesPerInherent;
+	type MaxBackedCandidatesPerPara = MaxBackedCandidatesPerPara;
+	type MaxDisputeStatementsPerBlock = MaxDisputeStatementsPerBlock;
+	type WeightInfo = ();
+	type WeightRefundPolicy = ParasInherentWeightRefundPolicy;
+	type MissingInherentHandler = ();
+	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
+	type BackingRewardHandler = ();
+	type CandidateFilter = ();
+	type TestMode = ParasInherentTestMode;
+}
 
 impl parachains_scheduler::Config for Runtime {}
 
@@ -1267,6 +1288,38 @@ sp_api::impl_runtime_apis! {
 		fn validation_code_by_hash(hash: ValidationCodeHash) -> Option<ValidationCode> {
 			runtime_api_impl::validation_code_by_hash::<Runtime>(hash)
 		}
+
+		fn last_backed_candidate_weight() -> u64 {
+			runtime_api_impl::last_backed_candidate_weight::<Runtime>()
+		}
+
+		fn dry_run_inherent(data: primitives::v1::InherentData) -> Result<u64, sp_runtime::DispatchError> {
+			runtime_api_impl::dry_run_inherent::<Runtime>(data)
+		}
+
+		fn pending_availability_count() -> u32 {
+			runtime_api_impl::pending_availability_count::<Runtime>()
+		}
+
+		fn pending_bitfields() -> Vec<ValidatorIndex> {
+			runtime_api_impl::pending_bitfields::<Runtime>()
+		}
+
+		fn last_included_block_number() -> Option<BlockNumber> {
+			runtime_api_impl::last_included_block_number::<Runtime>()
+		}
+
+		fn scheduled_cores_count() -> u32 {
+			runtime_api_impl::scheduled_cores_count::<Runtime>()
+		}
+
+		fn availability_core_occupancy() -> Vec<Option<Id>> {
+			runtime_api_impl::availability_core_occupancy::<Runtime>()
+		}
+
+		fn last_backed_candidate_hashes() -> Vec<primitives::v1::CandidateHash> {
+			runtime_api_impl::last_backed_candidate_hashes::<Runtime>()
+		}
 	}
 
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
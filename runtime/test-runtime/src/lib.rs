@@ -466,7 +466,28 @@ impl parachains_disputes::Config for Runtime {
 	type PunishValidators = ();
 }
 
-impl parachains_paras_inherent::Config for Runtime {}
+parameter_types! {
+	pub const MaxCodeUpgradesPerInherent: u32 = 1;
+	pub const MaxBackedCandidatesPerPara: u32 = 1;
+	pub const MaxDisputeStatementsPerBlock: u32 = 5_000;
+	pub const ParasInherentWeightRefundPolicy: parachains_paras_inherent::WeightRefundPolicy =
+		parachains_paras_inherent::WeightRefundPolicy::AlwaysRefund;
+	pub const ParasInherentTestMode: bool = true;
+}
+
+impl parachains_paras_inherent::Config for Runtime {
+	type Event = Event;
+	type MaxCodeUpgradesPerInherent = MaxCodeUpgradesPerInherent;
+	type MaxBackedCandidatesPerPara = MaxBackedCandidatesPerPara;
+	type MaxDisputeStatementsPerBlock = MaxDisputeStatementsPerBlock;
+	type WeightInfo = ();
+	type WeightRefundPolicy = ParasInherentWeightRefundPolicy;
+	type MissingInherentHandler = ();
+	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
+	type BackingRewardHandler = ();
+	type CandidateFilter = ();
+	type TestMode = ParasInherentTestMode;
+}
 
 impl parachains_initializer::Config for Runtime {
 	type Randomness = pallet_babe::RandomnessFromOneEpochAgo<Runtime>;
@@ -537,7 +558,7 @@ construct_runtime! {
 		// Parachains runtime modules
 		ParachainsConfiguration: parachains_configuration::{Pallet, Call, Storage, Config<T>},
 		ParaInclusion: parachains_inclusion::{Pallet, Call, Storage, Event<T>},
-		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent},
+		ParasInherent: parachains_paras_inherent::{Pallet, Call, Storage, Inherent, Event},
 		Initializer: parachains_initializer::{Pallet, Call, Storage},
 		Paras: parachains_paras::{Pallet, Call, Storage, Origin, Event},
 		ParasShared: parachains_shared::{Pallet, Call, Storage},
@@ -711,6 +732,38 @@ sp_api::impl_runtime_apis! {
 		fn validation_code_by_hash(hash: ValidationCodeHash) -> Option<ValidationCode> {
 			runtime_impl::validation_code_by_hash::<Runtime>(hash)
 		}
+
+		fn last_backed_candidate_weight() -> u64 {
+			runtime_impl::last_backed_candidate_weight::<Runtime>()
+		}
+
+		fn dry_run_inherent(data: primitives::v1::InherentData) -> Result<u64, sp_runtime::DispatchError> {
+			runtime_impl::dry_run_inherent::<Runtime>(data)
+		}
+
+		fn pending_availability_count() -> u32 {
+			runtime_impl::pending_availability_count::<Runtime>()
+		}
+
+		fn pending_bitfields() -> Vec<ValidatorIndex> {
+			runtime_impl::pending_bitfields::<Runtime>()
+		}
+
+		fn last_included_block_number() -> Option<BlockNumber> {
+			runtime_impl::last_included_block_number::<Runtime>()
+		}
+
+		fn scheduled_cores_count() -> u32 {
+			runtime_impl::scheduled_cores_count::<Runtime>()
+		}
+
+		fn availability_core_occupancy() -> Vec<Option<ParaId>> {
+			runtime_impl::availability_core_occupancy::<Runtime>()
+		}
+
+		fn last_backed_candidate_hashes() -> Vec<primitives::v1::CandidateHash> {
+			runtime_impl::last_backed_candidate_hashes::<Runtime>()
+		}
 	}
 
 	impl beefy_primitives::BeefyApi<Block> for Runtime {